@@ -0,0 +1,90 @@
+// $ cargo rb bench
+//
+// Compares per-pixel drawing with the `fill_solid` and `fill_contiguous` fast paths, in a
+// framebuffer and straight to the display.
+#![no_std]
+#![no_main]
+#![feature(type_alias_impl_trait)]
+
+use nrf_embassy as _; // global logger + panicking-behavior + memory layout
+
+use embassy::executor::Spawner;
+use embassy::time::{Delay, Instant};
+use embassy::util::Forever;
+use embassy_nrf::gpio::{Level, Output, OutputDrive};
+use embassy_nrf::{interrupt, spim, Peripherals};
+use embedded_graphics::{pixelcolor::Rgb565, prelude::*, primitives::Rectangle};
+use embedded_hal_async::spi::ExclusiveDevice;
+use st7735_embassy::{self, Frame, ST7735IF};
+
+const BUF_SIZE: usize = 160 * 128 * 2;
+static FRAME: Forever<Frame<BUF_SIZE>> = Forever::new();
+
+const RUNS: u64 = 10;
+
+#[embassy::main]
+async fn main(_spawner: Spawner, p: Peripherals) {
+    let mut config = spim::Config::default();
+    config.frequency = spim::Frequency::M32;
+    let irq = interrupt::take!(SPIM3);
+    let spim = spim::Spim::new_txonly(p.SPI3, irq, p.P0_04, p.P0_28, config);
+    let cs_pin = Output::new(p.P0_30, Level::Low, OutputDrive::Standard);
+    let spi_dev = ExclusiveDevice::new(spim, cs_pin);
+
+    let rst = Output::new(p.P0_31, Level::High, OutputDrive::Standard);
+    let dc = Output::new(p.P0_29, Level::High, OutputDrive::Standard);
+
+    let mut display = ST7735IF::new(spi_dev, dc, rst, Default::default())
+        .init(&mut Delay)
+        .await
+        .unwrap();
+    let _backlight = Output::new(p.P0_03, Level::High, OutputDrive::Standard);
+
+    let frame = FRAME.put(Default::default());
+    let area = Rectangle::new(Point::zero(), frame.size());
+    let pixels = || area.points().map(|p| Pixel(p, Rgb565::RED));
+    let colors = || core::iter::repeat(Rgb565::BLUE);
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        frame.draw_iter(pixels()).unwrap();
+    }
+    let per_pixel = start.elapsed().as_micros() / RUNS;
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        frame.fill_solid(&area, Rgb565::GREEN).unwrap();
+    }
+    let solid = start.elapsed().as_micros() / RUNS;
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        frame.fill_contiguous(&area, colors()).unwrap();
+    }
+    let contiguous = start.elapsed().as_micros() / RUNS;
+    defmt::info!(
+        "framebuffer: draw_iter {} us, fill_solid {} us, fill_contiguous {} us",
+        per_pixel,
+        solid,
+        contiguous
+    );
+
+    // Column by column, so every pixel gets its own address window.
+    let scattered = || {
+        let (width, height) = (area.size.width as i32, area.size.height as i32);
+        (0..width).flat_map(move |x| (0..height).map(move |y| Pixel(Point::new(x, y), Rgb565::RED)))
+    };
+    let start = Instant::now();
+    display.draw_iter(scattered()).await.unwrap();
+    let per_pixel = start.elapsed().as_micros();
+    let start = Instant::now();
+    display.fill_solid(&area, Rgb565::GREEN).await.unwrap();
+    let solid = start.elapsed().as_micros();
+    let start = Instant::now();
+    display.fill_contiguous(&area, colors()).await.unwrap();
+    let contiguous = start.elapsed().as_micros();
+    defmt::info!(
+        "direct: per-pixel windows {} us, fill_solid {} us, fill_contiguous {} us",
+        per_pixel,
+        solid,
+        contiguous
+    );
+}
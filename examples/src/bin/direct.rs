@@ -0,0 +1,50 @@
+// $ cargo rb direct
+#![no_std]
+#![no_main]
+#![feature(type_alias_impl_trait)]
+
+use nrf_embassy as _; // global logger + panicking-behavior + memory layout
+use tinybmp::Bmp;
+
+use embassy::executor::Spawner;
+use embassy::time::{Delay, Duration, Timer};
+use embassy_nrf::gpio::{Level, Output, OutputDrive};
+use embassy_nrf::{interrupt, spim, Peripherals};
+use embedded_graphics::{image::Image, pixelcolor::Rgb565, prelude::*};
+use embedded_hal_async::spi::ExclusiveDevice;
+use st7735_embassy::{self, ST7735IF};
+
+#[embassy::main]
+async fn main(_spawner: Spawner, p: Peripherals) {
+    let mut config = spim::Config::default();
+    config.frequency = spim::Frequency::M32;
+    let irq = interrupt::take!(SPIM3);
+    let spim = spim::Spim::new_txonly(p.SPI3, irq, p.P0_04, p.P0_28, config);
+    let cs_pin = Output::new(p.P0_30, Level::Low, OutputDrive::Standard);
+    let spi_dev = ExclusiveDevice::new(spim, cs_pin);
+
+    let rst = Output::new(p.P0_31, Level::High, OutputDrive::Standard);
+    let dc = Output::new(p.P0_29, Level::High, OutputDrive::Standard);
+
+    // No framebuffer: everything is drawn straight to the display.
+    let mut display = ST7735IF::new(spi_dev, dc, rst, Default::default())
+        .init(&mut Delay)
+        .await
+        .unwrap();
+    display
+        .fill_solid(&display.bounding_box(), Rgb565::BLACK)
+        .await
+        .unwrap();
+
+    let raw_image: Bmp<Rgb565> =
+        Bmp::from_slice(include_bytes!("../../assets/ferris.bmp")).unwrap();
+    let image = Image::new(&raw_image, Point::new(34, 24));
+    // Drawn 8 rows at a time through a small pixel buffer.
+    let mut pixels = [None; 160 * 8];
+    display.draw(&image, &mut pixels).await.unwrap();
+
+    let _backlight = Output::new(p.P0_03, Level::High, OutputDrive::Standard);
+    loop {
+        Timer::after(Duration::from_secs(1)).await;
+    }
+}
@@ -12,7 +12,7 @@ use embassy_nrf::gpio::{Level, Output, OutputDrive};
 use embassy_nrf::{interrupt, spim, Peripherals};
 use embedded_graphics::{image::Image, pixelcolor::Rgb565, prelude::*};
 use embedded_hal_async::spi::ExclusiveDevice;
-use st7735_embassy::{self, ST7735};
+use st7735_embassy::{self, Framebuffer, ST7735};
 
 #[embassy::main]
 async fn main(_spawner: Spawner, p: Peripherals) {
@@ -31,8 +31,11 @@ async fn main(_spawner: Spawner, p: Peripherals) {
 
     let dc = Output::new(p.P0_29, Level::High, OutputDrive::Standard);
 
-    let mut display = ST7735::new(spi_dev, dc, rst, Default::default(), 160, 128);
-    display.init(&mut Delay).await.unwrap();
+    let mut display: ST7735<_, _, _, 160, 128> =
+        ST7735::new(spi_dev, dc, rst, Default::default(), Framebuffer::new())
+            .init(&mut Delay)
+            .await
+            .unwrap();
     display.clear(Rgb565::BLACK).unwrap();
 
     let raw_image: Bmp<Rgb565> =
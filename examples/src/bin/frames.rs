@@ -4,9 +4,8 @@
 #![feature(type_alias_impl_trait)]
 use nrf_embassy as _; // global logger + panicking-behavior + memory layout
 
-use embassy::channel::signal::Signal;
 use embassy::executor::Spawner;
-use embassy::time::{Delay, Duration, Timer};
+use embassy::time::{Delay, Duration, Instant, Timer};
 use embassy::util::Forever;
 use embassy_nrf::{
     gpio::{Level, Output, OutputDrive},
@@ -17,29 +16,33 @@ use embassy_nrf::{
 };
 use embedded_graphics::{pixelcolor::Rgb565, prelude::*};
 use embedded_hal_async::spi::ExclusiveDevice;
-use st7735_embassy::{self, Frame, ST7735IF};
+use st7735_embassy::{self, FrameDiff, Flusher, Presenter, ST7735IF};
 
 const BUF_SIZE: usize = 160 * 128 * 2;
-static FRAME_A: Forever<Frame<BUF_SIZE>> = Forever::new();
-static FRAME_B: Forever<Frame<BUF_SIZE>> = Forever::new();
-static NEXT_FRAME: Forever<Signal<&'static mut Frame<BUF_SIZE>>> = Forever::new();
-static READY_FRAME: Forever<Signal<&'static mut Frame<BUF_SIZE>>> = Forever::new();
+type Display = Presenter<
+    ExclusiveDevice<Spim<'static, SPI3>, Output<'static, P0_24>>,
+    Output<'static, P0_20>,
+    Output<'static, P0_22>,
+    BUF_SIZE,
+    2,
+>;
+static PRESENTER: Forever<Display> = Forever::new();
 
 #[embassy::task]
-async fn render(
-    spi_dev: ExclusiveDevice<Spim<'static, SPI3>, Output<'static, P0_24>>,
-    dc: Output<'static, P0_20>,
-    rst: Output<'static, P0_22>,
-    next_frame: &'static Signal<&'static mut Frame<BUF_SIZE>>,
-    ready_frame: &'static Signal<&'static mut Frame<BUF_SIZE>>,
+async fn flush(
+    mut flusher: Flusher<
+        'static,
+        ExclusiveDevice<Spim<'static, SPI3>, Output<'static, P0_24>>,
+        Output<'static, P0_20>,
+        Output<'static, P0_22>,
+        BUF_SIZE,
+        2,
+    >,
 ) {
-    let mut display = ST7735IF::new(spi_dev, dc, rst, Default::default());
-    display.init(&mut Delay).await.unwrap();
-    let mut frame = ready_frame.wait().await;
+    // 16x16 pixel tiles, 10x8 of them on a 160x128 frame.
+    let mut diff = FrameDiff::<{ 10 * 8 }>::new(16, 16);
     loop {
-        next_frame.signal(frame);
-        frame = ready_frame.wait().await;
-        display.flush_frame(&frame).await.unwrap();
+        flusher.flush_next_diff(&mut diff).await.unwrap();
     }
 }
 
@@ -55,27 +58,38 @@ async fn main(spawner: Spawner, p: Peripherals) {
     let dc = Output::new(p.P0_20, Level::High, OutputDrive::Standard);
     let rst = Output::new(p.P0_22, Level::High, OutputDrive::Standard);
 
-    let next_frame = NEXT_FRAME.put(Signal::new());
-    let frame_a = FRAME_A.put(Default::default());
-    next_frame.signal(frame_a);
-
-    let ready_frame = READY_FRAME.put(Signal::new());
-    let frame_b = FRAME_B.put(Default::default());
-    ready_frame.signal(frame_b);
-
-    defmt::unwrap!(spawner.spawn(render(spi_dev, dc, rst, next_frame, ready_frame)));
+    let display = ST7735IF::new(spi_dev, dc, rst, Default::default())
+        .init(&mut Delay)
+        .await
+        .unwrap();
+    let presenter = PRESENTER.put(Presenter::new(
+        display,
+        [Default::default(), Default::default()],
+        || Instant::now().as_micros() as u32,
+    ));
+    let (mut canvas, flusher) = presenter.split();
+    defmt::unwrap!(spawner.spawn(flush(flusher)));
 
     let _backlight = Output::new(p.P0_13, Level::High, OutputDrive::Standard);
 
     let mut x = 0;
     let mut y = 0;
     loop {
-        let frame = next_frame.wait().await;
-        // frame.clear(Rgb565::BLACK).unwrap();
+        let frame = canvas.back_buffer().await;
+        frame.clear(Rgb565::BLACK).unwrap();
         frame.set_pixel(x, y, Rgb565::GREEN);
-        ready_frame.signal(frame);
+        canvas.present().await;
         x = (x + 1) % 160;
         y = (y + 1) % 128;
+        if x == 0 {
+            let stats = canvas.stats();
+            defmt::info!(
+                "{} frames, {} dropped, latency {} us",
+                stats.presented,
+                stats.dropped,
+                stats.last_latency
+            );
+        }
         Timer::after(Duration::from_millis(10)).await;
     }
 }
@@ -18,12 +18,18 @@ use embassy_nrf::{
 };
 use embedded_graphics::{image::Image, pixelcolor::Rgb565, prelude::*};
 use embassy_embedded_hal::shared_bus::spi::SpiBusDevice;
-use st7735_embassy::{self, ST7735};
+use st7735_embassy::{self, model, Uninitialized, ST7735};
+use static_cell::ConstStaticCell;
 use tinybmp::Bmp;
 
+const BUF_SIZE: usize = 160 * 128 * 2;
+// Keep the framebuffer in a zero-initialized static, so it lives in .bss and is never built on
+// the stack, and the display can be moved into a task cheaply.
+static FRAMEBUFFER: ConstStaticCell<[u8; BUF_SIZE]> = ConstStaticCell::new([0; BUF_SIZE]);
+
 #[embassy::task]
 async fn display_task(
-    mut display: ST7735<
+    display: ST7735<
         SpiBusDevice<
             'static,
             ThreadModeRawMutex,
@@ -32,9 +38,15 @@ async fn display_task(
         >,
         Output<'static, P0_20>,
         Output<'static, P0_22>,
+        160,
+        128,
+        &'static mut [u8; BUF_SIZE],
+        model::ST7735,
+        Rgb565,
+        Uninitialized,
     >,
 ) {
-    display.init(&mut Delay).await.unwrap();
+    let mut display = display.init(&mut Delay).await.unwrap();
     display.clear(Rgb565::BLACK).unwrap();
     let raw_image: Bmp<Rgb565> =
         Bmp::from_slice(include_bytes!("../../assets/ferris.bmp")).unwrap();
@@ -57,7 +69,8 @@ async fn main(spawner: Spawner, p: Peripherals) {
     let spi_dev = SpiBusDevice::new(spi_bus, cs_pin);
     let dc = Output::new(p.P0_20, Level::High, OutputDrive::Standard);
     let rst = Output::new(p.P0_22, Level::High, OutputDrive::Standard);
-    let display = ST7735::new(spi_dev, dc, rst, Default::default(), 160, 128);
+    let framebuffer = FRAMEBUFFER.take();
+    let display = ST7735::new(spi_dev, dc, rst, Default::default(), framebuffer);
     unwrap!(spawner.spawn(display_task(display)));
 
     let mut backlight = Output::new(p.P0_13, Level::High, OutputDrive::Standard);
@@ -0,0 +1,176 @@
+//! Rendering the display in horizontal bands, without a full framebuffer.
+
+use core::future::Future;
+use core::mem;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use embedded_graphics_core::{draw_target::DrawTarget, prelude::*, primitives::Rectangle};
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal_async::spi::{SpiBus, SpiDevice};
+
+use crate::error::comm;
+use crate::instruction::Instruction;
+use crate::{DisplayColor, DrawError, Error, Frame, Model, Op, ST7735IF};
+
+impl<SPI, DC, RST, M, E, DCE, RSTE> ST7735IF<SPI, DC, RST, M>
+where
+    SPI: SpiDevice<Error = E>,
+    SPI::Bus: SpiBus,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    M: Model,
+{
+    /// Renders the whole display band by band, calling `render` to draw each band.
+    ///
+    /// Bands are as wide as the display and as high as fits in `N` bytes. `render` draws in
+    /// display coordinates into a [`Band`], which drops whatever falls outside of it. While a band
+    /// is sent, the next one is drawn into the other frame of `bands`: the address window and RAMWR
+    /// go out first, then the pixel transfer is started and the next band drawn before waiting
+    /// for it. Drawing overlaps the transfer when the SPI bus transfers in the background, e.g.
+    /// with DMA.
+    ///
+    /// Bands keep what was drawn into them two bands before, `render` must clear them unless it
+    /// draws every pixel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` bytes don't hold one row of the display.
+    pub async fn render_bands<const N: usize, C, F>(
+        &mut self,
+        bands: &mut [Frame<N, C>; 2],
+        mut render: F,
+    ) -> Result<(), Error<E, DCE, RSTE>>
+    where
+        C: DisplayColor,
+        F: FnMut(&mut Band<'_, N, C>),
+    {
+        let size = self.size();
+        let band_height = (N * 8 / (size.width as usize * C::BITS)) as u32;
+        assert!(band_height > 0, "band buffer is smaller than a row");
+        self.select_color::<C>().await?;
+
+        let orientation = self.orientation;
+        let mut draw = |frame: &mut Frame<N, C>, y: u32| {
+            frame.width = size.width;
+            frame.height = band_height.min(size.height - y);
+            frame.orientation = orientation;
+            render(&mut Band { frame, y });
+        };
+
+        let (front, back) = bands.split_at_mut(1);
+        let (mut front, mut back) = (&mut front[0], &mut back[0]);
+        draw(front, 0);
+        let mut y = 0;
+        while y < size.height {
+            let next = y + band_height;
+            // Only the pixel transfer is overlapped, the commands before it are sent first.
+            self.start_band(front, y).await?;
+            let pixels = &front.buffer[..C::bytes((front.width * front.height) as usize)];
+            let write = async { self.spi.write(pixels).await.map_err(comm(Op::MemoryWrite)) };
+            if next < size.height {
+                overlap(write, || draw(back, next)).await?;
+            } else {
+                write.await?;
+            }
+            mem::swap(&mut front, &mut back);
+            y = next;
+        }
+        Ok(())
+    }
+
+    /// Prepares the rows of the display starting at `y` for the pixels of `frame`.
+    async fn start_band<const N: usize, C>(
+        &mut self,
+        frame: &Frame<N, C>,
+        y: u32,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        let (width, height) = (frame.width, frame.height);
+        self.set_address_window(0, y as u16, width as u16 - 1, (y + height) as u16 - 1)
+            .await?;
+        self.write_command(Instruction::RAMWR, &[]).await?;
+        self.start_data()
+    }
+}
+
+/// Polls `future` once so it can start, runs `work` and then waits for `future` to complete.
+async fn overlap<F: Future>(mut future: F, work: impl FnOnce()) -> F::Output {
+    // SAFETY: `future` is shadowed and never moved again.
+    let mut future = unsafe { Pin::new_unchecked(&mut future) };
+    let first = PollOnce(future.as_mut()).await;
+    work();
+    match first {
+        Some(output) => output,
+        None => future.await,
+    }
+}
+
+/// Polls the inner future once, completing with its output if it was ready.
+struct PollOnce<'a, F>(Pin<&'a mut F>);
+
+impl<F: Future> Future for PollOnce<'_, F> {
+    type Output = Option<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.0.as_mut().poll(cx) {
+            Poll::Ready(output) => Poll::Ready(Some(output)),
+            Poll::Pending => Poll::Ready(None),
+        }
+    }
+}
+
+/// Horizontal band of the display starting at row `y`, drawn into with
+/// [`ST7735IF::render_bands`].
+///
+/// Coordinates are those of the display, pixels outside of the band are dropped.
+pub struct Band<'a, const N: usize, C> {
+    frame: &'a mut Frame<N, C>,
+    y: u32,
+}
+
+impl<const N: usize, C> Band<'_, N, C> {
+    /// Moves `point` from display to frame coordinates.
+    fn to_frame(&self, point: Point) -> Point {
+        point - Point::new(0, self.y as i32)
+    }
+}
+
+impl<const N: usize, C: DisplayColor> DrawTarget for Band<'_, N, C> {
+    type Error = DrawError;
+    type Color = C;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let y = Point::new(0, self.y as i32);
+        self.frame.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(pos, color)| Pixel(pos - y, color)),
+        )
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let area = Rectangle::new(self.to_frame(area.top_left), area.size);
+        self.frame.fill_contiguous(&area, colors)
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = Rectangle::new(self.to_frame(area.top_left), area.size);
+        self.frame.fill_solid(&area, color)
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.frame.clear(color)
+    }
+}
+
+impl<const N: usize, C> Dimensions for Band<'_, N, C> {
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(Point::new(0, self.y as i32), self.frame.size())
+    }
+}
@@ -0,0 +1,275 @@
+//! Pixel formats of the display.
+
+use embedded_graphics_core::pixelcolor::{
+    raw::{RawData, RawU16},
+    Bgr565, Bgr666, PixelColor, Rgb565, Rgb666, Rgb888, RgbColor,
+};
+
+use crate::config::ColorOrder;
+use crate::model::ColorMode;
+
+/// Color types that can be stored in a framebuffer and sent to the display.
+///
+/// Pixels are packed the way the display reads them in the color mode of the type: RGB444 packs
+/// two pixels in three bytes, RGB565 uses two big-endian bytes and RGB666 three bytes with the
+/// channels in the upper six bits.
+///
+/// BGR types are sent in their own channel order, the driver flips the MADCTL color order bit so
+/// they show up right on the panel.
+pub trait DisplayColor: PixelColor {
+    /// Color mode the display must be set to for this type.
+    const COLOR_MODE: ColorMode;
+    /// Order of the channels as sent, the first one is stored in the upper bits.
+    const COLOR_ORDER: ColorOrder = ColorOrder::Rgb;
+    /// Bits per pixel, in buffers and on the wire.
+    const BITS: usize;
+    /// Number of pixels a write must be aligned to, so it starts and ends on a byte boundary.
+    const ALIGN: usize;
+
+    /// Stores the color as pixel `index` of `buffer`.
+    fn store(self, buffer: &mut [u8], index: usize);
+
+    /// Reads pixel `index` of `buffer`.
+    fn load(buffer: &[u8], index: usize) -> Self;
+
+    /// Bytes used by `pixels` pixels.
+    fn bytes(pixels: usize) -> usize {
+        (pixels * Self::BITS).div_ceil(8)
+    }
+}
+
+impl DisplayColor for Rgb444 {
+    const COLOR_MODE: ColorMode = ColorMode::Rgb444;
+    const BITS: usize = 12;
+    const ALIGN: usize = 2;
+
+    fn store(self, buffer: &mut [u8], index: usize) {
+        let i = index / 2 * 3;
+        if index.is_multiple_of(2) {
+            buffer[i] = self.r() << 4 | self.g();
+            buffer[i + 1] = self.b() << 4 | (buffer[i + 1] & 0x0F);
+        } else {
+            buffer[i + 1] = (buffer[i + 1] & 0xF0) | self.r();
+            buffer[i + 2] = self.g() << 4 | self.b();
+        }
+    }
+
+    fn load(buffer: &[u8], index: usize) -> Self {
+        let i = index / 2 * 3;
+        if index.is_multiple_of(2) {
+            Self::new(buffer[i] >> 4, buffer[i] & 0xF, buffer[i + 1] >> 4)
+        } else {
+            Self::new(buffer[i + 1] & 0xF, buffer[i + 2] >> 4, buffer[i + 2] & 0xF)
+        }
+    }
+}
+
+impl DisplayColor for Rgb565 {
+    const COLOR_MODE: ColorMode = ColorMode::Rgb565;
+    const BITS: usize = 16;
+    const ALIGN: usize = 1;
+
+    fn store(self, buffer: &mut [u8], index: usize) {
+        buffer[index * 2..index * 2 + 2]
+            .copy_from_slice(&RawU16::from(self).into_inner().to_be_bytes());
+    }
+
+    fn load(buffer: &[u8], index: usize) -> Self {
+        RawU16::new(u16::from_be_bytes([
+            buffer[index * 2],
+            buffer[index * 2 + 1],
+        ]))
+        .into()
+    }
+}
+
+impl DisplayColor for Bgr565 {
+    const COLOR_MODE: ColorMode = ColorMode::Rgb565;
+    const COLOR_ORDER: ColorOrder = ColorOrder::Bgr;
+    const BITS: usize = 16;
+    const ALIGN: usize = 1;
+
+    fn store(self, buffer: &mut [u8], index: usize) {
+        buffer[index * 2..index * 2 + 2]
+            .copy_from_slice(&RawU16::from(self).into_inner().to_be_bytes());
+    }
+
+    fn load(buffer: &[u8], index: usize) -> Self {
+        RawU16::new(u16::from_be_bytes([
+            buffer[index * 2],
+            buffer[index * 2 + 1],
+        ]))
+        .into()
+    }
+}
+
+impl DisplayColor for Rgb666 {
+    const COLOR_MODE: ColorMode = ColorMode::Rgb666;
+    const BITS: usize = 24;
+    const ALIGN: usize = 1;
+
+    fn store(self, buffer: &mut [u8], index: usize) {
+        buffer[index * 3..index * 3 + 3].copy_from_slice(&[
+            self.r() << 2,
+            self.g() << 2,
+            self.b() << 2,
+        ]);
+    }
+
+    fn load(buffer: &[u8], index: usize) -> Self {
+        let i = index * 3;
+        Self::new(buffer[i] >> 2, buffer[i + 1] >> 2, buffer[i + 2] >> 2)
+    }
+}
+
+impl DisplayColor for Bgr666 {
+    const COLOR_MODE: ColorMode = ColorMode::Rgb666;
+    const COLOR_ORDER: ColorOrder = ColorOrder::Bgr;
+    const BITS: usize = 24;
+    const ALIGN: usize = 1;
+
+    fn store(self, buffer: &mut [u8], index: usize) {
+        buffer[index * 3..index * 3 + 3].copy_from_slice(&[
+            self.b() << 2,
+            self.g() << 2,
+            self.r() << 2,
+        ]);
+    }
+
+    fn load(buffer: &[u8], index: usize) -> Self {
+        let i = index * 3;
+        Self::new(buffer[i + 2] >> 2, buffer[i + 1] >> 2, buffer[i] >> 2)
+    }
+}
+
+/// 12 bit RGB color, with 4 bits per channel.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub struct Rgb444(u16);
+
+impl Rgb444 {
+    /// Creates a color, discarding the unused upper bits of each channel.
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self(((r & 0xF) as u16) << 8 | ((g & 0xF) as u16) << 4 | (b & 0xF) as u16)
+    }
+}
+
+impl RgbColor for Rgb444 {
+    fn r(&self) -> u8 {
+        (self.0 >> 8) as u8 & 0xF
+    }
+
+    fn g(&self) -> u8 {
+        (self.0 >> 4) as u8 & 0xF
+    }
+
+    fn b(&self) -> u8 {
+        self.0 as u8 & 0xF
+    }
+
+    const MAX_R: u8 = 0xF;
+    const MAX_G: u8 = 0xF;
+    const MAX_B: u8 = 0xF;
+
+    const BLACK: Self = Self::new(0, 0, 0);
+    const RED: Self = Self::new(0xF, 0, 0);
+    const GREEN: Self = Self::new(0, 0xF, 0);
+    const BLUE: Self = Self::new(0, 0, 0xF);
+    const YELLOW: Self = Self::new(0xF, 0xF, 0);
+    const MAGENTA: Self = Self::new(0xF, 0, 0xF);
+    const CYAN: Self = Self::new(0, 0xF, 0xF);
+    const WHITE: Self = Self::new(0xF, 0xF, 0xF);
+}
+
+impl PixelColor for Rgb444 {
+    type Raw = RawU16;
+}
+
+impl From<RawU16> for Rgb444 {
+    fn from(raw: RawU16) -> Self {
+        Self(raw.into_inner() & 0x0FFF)
+    }
+}
+
+impl From<Rgb444> for RawU16 {
+    fn from(color: Rgb444) -> Self {
+        RawU16::new(color.0)
+    }
+}
+
+/// Scales a channel `value` from `0..=from_max` to `0..=to_max`, rounding to the nearest value.
+const fn convert_channel(value: u8, from_max: u8, to_max: u8) -> u8 {
+    ((value as u16 * to_max as u16 + from_max as u16 / 2) / from_max as u16) as u8
+}
+
+macro_rules! impl_rgb444_conversion {
+    ($($type:ident),*) => {
+        $(
+            impl From<$type> for Rgb444 {
+                fn from(color: $type) -> Self {
+                    Self::new(
+                        convert_channel(color.r(), $type::MAX_R, Rgb444::MAX_R),
+                        convert_channel(color.g(), $type::MAX_G, Rgb444::MAX_G),
+                        convert_channel(color.b(), $type::MAX_B, Rgb444::MAX_B),
+                    )
+                }
+            }
+
+            impl From<Rgb444> for $type {
+                fn from(color: Rgb444) -> Self {
+                    $type::new(
+                        convert_channel(color.r(), Rgb444::MAX_R, $type::MAX_R),
+                        convert_channel(color.g(), Rgb444::MAX_G, $type::MAX_G),
+                        convert_channel(color.b(), Rgb444::MAX_B, $type::MAX_B),
+                    )
+                }
+            }
+        )*
+    };
+}
+
+impl_rgb444_conversion!(Rgb565, Rgb666, Rgb888);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgb444_packs_two_pixels_in_three_bytes() {
+        let mut buffer = [0; 3];
+        Rgb444::new(0x1, 0x2, 0x3).store(&mut buffer, 0);
+        Rgb444::new(0x4, 0x5, 0x6).store(&mut buffer, 1);
+        assert_eq!(buffer, [0x12, 0x34, 0x56]);
+        Rgb444::WHITE.store(&mut buffer, 0);
+        assert_eq!(buffer, [0xFF, 0xF4, 0x56]);
+        assert_eq!(Rgb444::bytes(3), 5);
+        assert_eq!(Rgb444::load(&buffer, 0), Rgb444::WHITE);
+        assert_eq!(Rgb444::load(&buffer, 1), Rgb444::new(0x4, 0x5, 0x6));
+    }
+
+    #[test]
+    fn rgb666_uses_upper_bits() {
+        let mut buffer = [0; 3];
+        Rgb666::new(0x3F, 0x01, 0x20).store(&mut buffer, 0);
+        assert_eq!(buffer, [0xFC, 0x04, 0x80]);
+        Bgr666::new(0x3F, 0x01, 0x20).store(&mut buffer, 0);
+        assert_eq!(buffer, [0x80, 0x04, 0xFC]);
+        assert_eq!(Bgr666::load(&buffer, 0), Bgr666::new(0x3F, 0x01, 0x20));
+    }
+
+    #[test]
+    fn bgr565_sends_blue_first() {
+        let mut buffer = [0; 2];
+        Bgr565::BLUE.store(&mut buffer, 0);
+        assert_eq!(buffer, [0xF8, 0x00]);
+    }
+
+    #[test]
+    fn rgb444_conversions_round_trip() {
+        for value in 0..=0xF {
+            let color = Rgb444::new(value, value, value);
+            assert_eq!(Rgb444::from(Rgb565::from(color)), color);
+            assert_eq!(Rgb444::from(Rgb888::from(color)), color);
+        }
+        assert_eq!(Rgb888::from(Rgb444::WHITE), Rgb888::WHITE);
+    }
+}
@@ -0,0 +1,286 @@
+//! Display configuration.
+
+use crate::model::{self, ColorMode, Model};
+use crate::Orientation;
+
+/// Display configuration.
+///
+/// Use [`Config::default`] for a black tab ST7735 module, or [`Config::builder`] for anything
+/// else.
+pub struct Config<M: Model = model::ST7735> {
+    pub(crate) rgb: bool,
+    pub(crate) inverted: bool,
+    pub(crate) orientation: Orientation,
+    pub(crate) color_mode: ColorMode,
+    pub(crate) variant: M::Variant,
+    /// Visible panel size in the native orientation.
+    pub(crate) size: (u16, u16),
+    /// Offset of the visible area in frame memory, in the native orientation.
+    pub(crate) offset: (u16, u16),
+    /// Length of the reset pulse in µs and time to wait after a reset in ms.
+    pub(crate) reset_timing: (u32, u32),
+}
+
+impl<M: Model> Config<M> {
+    /// Starts building a configuration for `model`.
+    pub fn builder(_model: M) -> ConfigBuilder<M> {
+        ConfigBuilder {
+            rgb: true,
+            inverted: false,
+            orientation: Orientation::Landscape,
+            color_mode: ColorMode::Rgb565,
+            variant: M::Variant::default(),
+            size: None,
+            offset: None,
+            reset_timing: RESET_TIMING,
+        }
+    }
+
+    /// Visible panel size in the native orientation.
+    pub fn size(&self) -> (u16, u16) {
+        self.size
+    }
+
+    /// Visible size in the configured orientation.
+    pub fn logical_size(&self) -> (u16, u16) {
+        if self.orientation.swap_axes() {
+            (self.size.1, self.size.0)
+        } else {
+            self.size
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let variant = model::ST7735Variant::default();
+        Self {
+            rgb: true,
+            inverted: false,
+            orientation: Orientation::Landscape,
+            color_mode: ColorMode::Rgb565,
+            variant,
+            size: model::ST7735::size(variant),
+            offset: model::ST7735::offset(variant),
+            reset_timing: RESET_TIMING,
+        }
+    }
+}
+
+/// Default reset pulse of 10 ms and the 120 ms the controller needs after a reset before it
+/// accepts SLPOUT.
+const RESET_TIMING: (u32, u32) = (10_000, 120);
+
+/// Subpixel order of the panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorOrder {
+    Rgb,
+    Bgr,
+}
+
+/// Builder for [`Config`], created with [`Config::builder`].
+///
+/// Size and offset default to the values of the selected variant.
+pub struct ConfigBuilder<M: Model> {
+    rgb: bool,
+    inverted: bool,
+    orientation: Orientation,
+    color_mode: ColorMode,
+    variant: M::Variant,
+    size: Option<(u16, u16)>,
+    offset: Option<(u16, u16)>,
+    reset_timing: (u32, u32),
+}
+
+impl<M: Model> ConfigBuilder<M> {
+    /// Selects the module revision, see [`model::ST7735Variant`].
+    pub fn variant(mut self, variant: M::Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Sets the visible panel size in the native (portrait) orientation.
+    pub fn size(mut self, width: u16, height: u16) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Sets the offset of the visible area in frame memory, in the native (portrait) orientation.
+    pub fn offset(mut self, dx: u16, dy: u16) -> Self {
+        self.offset = Some((dx, dy));
+        self
+    }
+
+    /// Sets the subpixel order.
+    ///
+    /// This is the order of the panel itself, the driver still sends RGB and BGR color types in
+    /// their own order.
+    pub fn color_order(mut self, order: ColorOrder) -> Self {
+        self.rgb = order == ColorOrder::Rgb;
+        self
+    }
+
+    /// Sets whether the colours are inverted.
+    pub fn inverted(mut self, inverted: bool) -> Self {
+        self.inverted = inverted;
+        self
+    }
+
+    /// Sets the initial orientation.
+    pub fn orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Sets the pixel format sent to the display.
+    ///
+    /// [`ST7735`](crate::ST7735) requires the color mode of its color type, e.g.
+    /// [`ColorMode::Rgb444`] for [`Rgb444`](crate::Rgb444).
+    pub fn color_mode(mut self, color_mode: ColorMode) -> Self {
+        self.color_mode = color_mode;
+        self
+    }
+
+    /// Sets the length of the pulse on the reset pin in µs and the time to wait after a hardware
+    /// or software reset in ms.
+    ///
+    /// The controller needs a pulse of at least 10 µs and 120 ms after a reset. Longer times
+    /// help with slow reset circuits, e.g. an RC network on the reset line.
+    pub fn reset_timing(mut self, pulse_us: u32, settle_ms: u32) -> Self {
+        self.reset_timing = (pulse_us, settle_ms);
+        self
+    }
+
+    /// Validates the configuration.
+    pub fn build(self) -> Result<Config<M>, ConfigError> {
+        let size = self.size.unwrap_or_else(|| M::size(self.variant));
+        let offset = self.offset.unwrap_or_else(|| M::offset(self.variant));
+
+        if size.0 == 0 || size.1 == 0 {
+            return Err(ConfigError::EmptySize);
+        }
+        let ram = M::ram_size(self.variant);
+        if size.0 as u32 + offset.0 as u32 > ram.0 as u32
+            || size.1 as u32 + offset.1 as u32 > ram.1 as u32
+        {
+            return Err(ConfigError::ExceedsRam);
+        }
+        if !M::COLOR_MODES.contains(&self.color_mode) {
+            return Err(ConfigError::UnsupportedColorMode);
+        }
+
+        Ok(Config {
+            rgb: self.rgb,
+            inverted: self.inverted,
+            orientation: self.orientation,
+            color_mode: self.color_mode,
+            variant: self.variant,
+            size,
+            offset,
+            reset_timing: self.reset_timing,
+        })
+    }
+}
+
+/// Invalid configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    /// Width or height is zero.
+    EmptySize,
+    /// The visible area does not fit in the controller's frame memory.
+    ExceedsRam,
+    /// The color mode is not supported.
+    UnsupportedColorMode,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Command, ST7735Variant};
+
+    /// Controller that only supports 16 bit colors.
+    struct Rgb565Only;
+
+    impl Model for Rgb565Only {
+        type Variant = ();
+        const RAM_WIDTH: u16 = 132;
+        const RAM_HEIGHT: u16 = 162;
+        const COLOR_MODES: &'static [ColorMode] = &[ColorMode::Rgb565];
+
+        fn size(_: ()) -> (u16, u16) {
+            (128, 160)
+        }
+
+        fn offset(_: ()) -> (u16, u16) {
+            (2, 1)
+        }
+
+        fn init_sequence(_: ()) -> &'static [Command<'static>] {
+            &[]
+        }
+    }
+
+    #[test]
+    fn defaults_follow_the_variant() {
+        let config = Config::builder(model::ST7735)
+            .variant(ST7735Variant::ST7735S)
+            .build()
+            .unwrap();
+        assert_eq!(config.size, (80, 160));
+        assert_eq!(config.offset, (26, 1));
+
+        let config = Config::builder(model::ST7735)
+            .variant(ST7735Variant::GreenTab128x128)
+            .build()
+            .unwrap();
+        assert_eq!(config.size, (128, 128));
+        assert_eq!(config.offset, (2, 1));
+
+        let config = Config::builder(model::ST7735).build().unwrap();
+        let default = Config::default();
+        assert_eq!((config.size, config.offset), (default.size, default.offset));
+    }
+
+    #[test]
+    fn rejects_invalid_geometry() {
+        let build = |size: (u16, u16), offset: (u16, u16)| {
+            Config::builder(model::ST7735)
+                .variant(ST7735Variant::GreenTab)
+                .size(size.0, size.1)
+                .offset(offset.0, offset.1)
+                .build()
+                .map(|_| ())
+        };
+        assert_eq!(build((0, 160), (0, 0)), Err(ConfigError::EmptySize));
+        assert_eq!(build((128, 0), (0, 0)), Err(ConfigError::EmptySize));
+        // The green tab uses the whole 132x162 frame memory.
+        assert_eq!(build((128, 160), (4, 2)), Ok(()));
+        assert_eq!(build((128, 160), (5, 2)), Err(ConfigError::ExceedsRam));
+        assert_eq!(build((128, 160), (4, 3)), Err(ConfigError::ExceedsRam));
+        // Black tab modules are strapped to 128x160, leaving no room for an offset.
+        let black_tab = Config::builder(model::ST7735)
+            .variant(ST7735Variant::BlackTab)
+            .offset(1, 0)
+            .build();
+        assert!(matches!(black_tab, Err(ConfigError::ExceedsRam)));
+    }
+
+    #[test]
+    fn rejects_unsupported_color_modes() {
+        let build = |mode| {
+            Config::builder(Rgb565Only)
+                .color_mode(mode)
+                .build()
+                .map(|_| ())
+        };
+        assert_eq!(build(ColorMode::Rgb565), Ok(()));
+        assert_eq!(
+            build(ColorMode::Rgb444),
+            Err(ConfigError::UnsupportedColorMode)
+        );
+        assert_eq!(
+            build(ColorMode::Rgb666),
+            Err(ConfigError::UnsupportedColorMode)
+        );
+    }
+}
@@ -0,0 +1,770 @@
+//! Scrolling text console drawn straight to the display.
+
+use core::fmt;
+
+use embedded_graphics_core::{
+    pixelcolor::{Rgb565, Rgb888},
+    prelude::*,
+};
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal_async::spi::{SpiBus, SpiDevice};
+
+use crate::dirty::Rect;
+use crate::{DisplayColor, Error, Model, ST7735IF};
+
+/// Monospace font of glyphs up to 8 pixels high.
+///
+/// Glyphs are stored column by column, one byte per column with the top pixel in the lowest bit.
+#[derive(Debug, Clone, Copy)]
+pub struct Font {
+    /// Glyph width in pixels, which is also the number of bytes per glyph.
+    pub width: u8,
+    /// Glyph height in pixels, at most 8.
+    pub height: u8,
+    /// First character of `glyphs`, the others follow in order.
+    pub first: char,
+    pub glyphs: &'static [u8],
+}
+
+impl Font {
+    /// Columns of the glyph of `c`, or `None` if the font has none.
+    fn glyph(&self, c: char) -> Option<&'static [u8]> {
+        let width = self.width as usize;
+        let index = (c as u32).checked_sub(self.first as u32)? as usize;
+        self.glyphs.get(index * width..(index + 1) * width)
+    }
+}
+
+/// 5x7 pixel font of the printable ASCII characters.
+pub const FONT_5X7: Font = Font {
+    width: 5,
+    height: 7,
+    first: ' ',
+    glyphs: &[
+        0x00, 0x00, 0x00, 0x00, 0x00, // ' '
+        0x00, 0x00, 0x5F, 0x00, 0x00, // !
+        0x00, 0x07, 0x00, 0x07, 0x00, // "
+        0x14, 0x7F, 0x14, 0x7F, 0x14, // #
+        0x24, 0x2A, 0x7F, 0x2A, 0x12, // $
+        0x23, 0x13, 0x08, 0x64, 0x62, // %
+        0x36, 0x49, 0x55, 0x22, 0x50, // &
+        0x00, 0x05, 0x03, 0x00, 0x00, // '
+        0x00, 0x1C, 0x22, 0x41, 0x00, // (
+        0x00, 0x41, 0x22, 0x1C, 0x00, // )
+        0x08, 0x2A, 0x1C, 0x2A, 0x08, // *
+        0x08, 0x08, 0x3E, 0x08, 0x08, // +
+        0x00, 0x50, 0x30, 0x00, 0x00, // ,
+        0x08, 0x08, 0x08, 0x08, 0x08, // -
+        0x00, 0x60, 0x60, 0x00, 0x00, // .
+        0x20, 0x10, 0x08, 0x04, 0x02, // /
+        0x3E, 0x51, 0x49, 0x45, 0x3E, // 0
+        0x00, 0x42, 0x7F, 0x40, 0x00, // 1
+        0x42, 0x61, 0x51, 0x49, 0x46, // 2
+        0x21, 0x41, 0x45, 0x4B, 0x31, // 3
+        0x18, 0x14, 0x12, 0x7F, 0x10, // 4
+        0x27, 0x45, 0x45, 0x45, 0x39, // 5
+        0x3C, 0x4A, 0x49, 0x49, 0x30, // 6
+        0x01, 0x71, 0x09, 0x05, 0x03, // 7
+        0x36, 0x49, 0x49, 0x49, 0x36, // 8
+        0x06, 0x49, 0x49, 0x29, 0x1E, // 9
+        0x00, 0x36, 0x36, 0x00, 0x00, // :
+        0x00, 0x56, 0x36, 0x00, 0x00, // ;
+        0x08, 0x14, 0x22, 0x41, 0x00, // <
+        0x14, 0x14, 0x14, 0x14, 0x14, // =
+        0x00, 0x41, 0x22, 0x14, 0x08, // >
+        0x02, 0x01, 0x51, 0x09, 0x06, // ?
+        0x32, 0x49, 0x79, 0x41, 0x3E, // @
+        0x7E, 0x11, 0x11, 0x11, 0x7E, // A
+        0x7F, 0x49, 0x49, 0x49, 0x36, // B
+        0x3E, 0x41, 0x41, 0x41, 0x22, // C
+        0x7F, 0x41, 0x41, 0x22, 0x1C, // D
+        0x7F, 0x49, 0x49, 0x49, 0x41, // E
+        0x7F, 0x09, 0x09, 0x09, 0x01, // F
+        0x3E, 0x41, 0x49, 0x49, 0x7A, // G
+        0x7F, 0x08, 0x08, 0x08, 0x7F, // H
+        0x00, 0x41, 0x7F, 0x41, 0x00, // I
+        0x20, 0x40, 0x41, 0x3F, 0x01, // J
+        0x7F, 0x08, 0x14, 0x22, 0x41, // K
+        0x7F, 0x40, 0x40, 0x40, 0x40, // L
+        0x7F, 0x02, 0x0C, 0x02, 0x7F, // M
+        0x7F, 0x04, 0x08, 0x10, 0x7F, // N
+        0x3E, 0x41, 0x41, 0x41, 0x3E, // O
+        0x7F, 0x09, 0x09, 0x09, 0x06, // P
+        0x3E, 0x41, 0x51, 0x21, 0x5E, // Q
+        0x7F, 0x09, 0x19, 0x29, 0x46, // R
+        0x46, 0x49, 0x49, 0x49, 0x31, // S
+        0x01, 0x01, 0x7F, 0x01, 0x01, // T
+        0x3F, 0x40, 0x40, 0x40, 0x3F, // U
+        0x1F, 0x20, 0x40, 0x20, 0x1F, // V
+        0x3F, 0x40, 0x38, 0x40, 0x3F, // W
+        0x63, 0x14, 0x08, 0x14, 0x63, // X
+        0x07, 0x08, 0x70, 0x08, 0x07, // Y
+        0x61, 0x51, 0x49, 0x45, 0x43, // Z
+        0x00, 0x7F, 0x41, 0x41, 0x00, // [
+        0x02, 0x04, 0x08, 0x10, 0x20, // \
+        0x00, 0x41, 0x41, 0x7F, 0x00, // ]
+        0x04, 0x02, 0x01, 0x02, 0x04, // ^
+        0x40, 0x40, 0x40, 0x40, 0x40, // _
+        0x00, 0x01, 0x02, 0x04, 0x00, // `
+        0x20, 0x54, 0x54, 0x54, 0x78, // a
+        0x7F, 0x48, 0x44, 0x44, 0x38, // b
+        0x38, 0x44, 0x44, 0x44, 0x20, // c
+        0x38, 0x44, 0x44, 0x48, 0x7F, // d
+        0x38, 0x54, 0x54, 0x54, 0x18, // e
+        0x08, 0x7E, 0x09, 0x01, 0x02, // f
+        0x0C, 0x52, 0x52, 0x52, 0x3E, // g
+        0x7F, 0x08, 0x04, 0x04, 0x78, // h
+        0x00, 0x44, 0x7D, 0x40, 0x00, // i
+        0x20, 0x40, 0x44, 0x3D, 0x00, // j
+        0x7F, 0x10, 0x28, 0x44, 0x00, // k
+        0x00, 0x41, 0x7F, 0x40, 0x00, // l
+        0x7C, 0x04, 0x18, 0x04, 0x78, // m
+        0x7C, 0x08, 0x04, 0x04, 0x78, // n
+        0x38, 0x44, 0x44, 0x44, 0x38, // o
+        0x7C, 0x14, 0x14, 0x14, 0x08, // p
+        0x08, 0x14, 0x14, 0x18, 0x7C, // q
+        0x7C, 0x08, 0x04, 0x04, 0x08, // r
+        0x48, 0x54, 0x54, 0x54, 0x20, // s
+        0x04, 0x3F, 0x44, 0x40, 0x20, // t
+        0x3C, 0x40, 0x40, 0x20, 0x7C, // u
+        0x1C, 0x20, 0x40, 0x20, 0x1C, // v
+        0x3C, 0x40, 0x30, 0x40, 0x3C, // w
+        0x44, 0x28, 0x10, 0x28, 0x44, // x
+        0x0C, 0x50, 0x50, 0x50, 0x3C, // y
+        0x44, 0x64, 0x54, 0x4C, 0x44, // z
+        0x00, 0x08, 0x36, 0x41, 0x00, // {
+        0x00, 0x00, 0x7F, 0x00, 0x00, // |
+        0x00, 0x41, 0x36, 0x08, 0x00, // }
+        0x08, 0x04, 0x08, 0x10, 0x08, // ~
+    ],
+};
+
+/// Standard and bright ANSI colors.
+const ANSI_COLORS: [Rgb888; 16] = [
+    Rgb888::new(0, 0, 0),
+    Rgb888::new(170, 0, 0),
+    Rgb888::new(0, 170, 0),
+    Rgb888::new(170, 85, 0),
+    Rgb888::new(0, 0, 170),
+    Rgb888::new(170, 0, 170),
+    Rgb888::new(0, 170, 170),
+    Rgb888::new(170, 170, 170),
+    Rgb888::new(85, 85, 85),
+    Rgb888::new(255, 85, 85),
+    Rgb888::new(85, 255, 85),
+    Rgb888::new(255, 255, 85),
+    Rgb888::new(85, 85, 255),
+    Rgb888::new(255, 85, 255),
+    Rgb888::new(85, 255, 255),
+    Rgb888::new(255, 255, 255),
+];
+
+/// Maximum number of parameters of an escape sequence, further ones are ignored.
+const MAX_PARAMS: usize = 4;
+
+/// Bytes of formatted text [`Console::write_fmt`] writes at a time.
+const FMT_CHUNK: usize = 64;
+
+/// Position in an escape sequence.
+#[derive(Clone, Copy)]
+enum Escape {
+    None,
+    /// After `ESC`.
+    Start,
+    /// After `ESC [`, with the parameters so far.
+    Csi {
+        params: [u16; MAX_PARAMS],
+        len: usize,
+    },
+}
+
+/// Text console writing straight to the display, created with [`Console::new`].
+///
+/// Characters are drawn into one cell each, one address window per character. When the last line
+/// is full the lines move up with the vertical scroll registers, so nothing but the new line is
+/// sent. Hardware scrolling follows the rows of the native orientation, so in landscape
+/// orientations the console starts again at the top line instead.
+///
+/// Handles `\n`, `\r`, `\t` and backspace, and the escape sequences `ESC[...m` for colors
+/// (0, 30-37, 39, 40-47, 49, 90-97 and 100-107), `ESC[K` to clear the line (0, 1, 2), `ESC[2J`
+/// to clear the screen and `ESC[H` to move the cursor home or to `ESC[row;colH`.
+pub struct Console<'a, SPI, DC, RST, M, C = Rgb565>
+where
+    SPI: SpiDevice,
+    SPI::Bus: SpiBus,
+    DC: OutputPin,
+    RST: OutputPin,
+    M: Model,
+    C: DisplayColor,
+{
+    iface: &'a mut ST7735IF<SPI, DC, RST, M>,
+    font: Font,
+    term: Terminal<C>,
+    /// Whether lines move with the scroll registers.
+    hardware_scroll: bool,
+}
+
+impl<'a, SPI, DC, RST, M, C, E, DCE, RSTE> Console<'a, SPI, DC, RST, M, C>
+where
+    SPI: SpiDevice<Error = E>,
+    SPI::Bus: SpiBus,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    M: Model,
+    C: DisplayColor + From<Rgb888>,
+{
+    /// Creates a console covering the display in its current orientation and clears it to `bg`.
+    ///
+    /// Each character takes one pixel more than the `font` in both directions. Pixel rows left
+    /// below the last line are not used.
+    ///
+    /// Fails with [`Error::InvalidGeometry`] if the display is smaller than one character.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the font is higher than 8 pixels.
+    pub async fn new(
+        iface: &'a mut ST7735IF<SPI, DC, RST, M>,
+        font: Font,
+        fg: C,
+        bg: C,
+    ) -> Result<Console<'a, SPI, DC, RST, M, C>, Error<E, DCE, RSTE>> {
+        assert!(font.height <= 8, "font is higher than 8 pixels");
+        let size = iface.size();
+        let (cell_width, cell_height) = (font.width as u32 + 1, font.height as u32 + 1);
+        if size.width < cell_width || size.height < cell_height {
+            return Err(Error::InvalidGeometry);
+        }
+        let hardware_scroll = !iface.orientation.swap_axes();
+        // Keep the rows below the last line out of the scroll area.
+        let unused = if hardware_scroll {
+            (size.height % cell_height) as u16
+        } else {
+            0
+        };
+        if iface.orientation.mirror_y() {
+            iface.set_scroll_area(unused, 0).await?;
+        } else {
+            iface.set_scroll_area(0, unused).await?;
+        }
+        let mut console = Self {
+            iface,
+            font,
+            term: Terminal::new(
+                (size.width / cell_width) as u16,
+                (size.height / cell_height) as u16,
+                fg,
+                bg,
+            ),
+            hardware_scroll,
+        };
+        console.clear().await?;
+        Ok(console)
+    }
+
+    /// Clears the screen to the background color and moves the cursor home.
+    pub async fn clear(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        let bb = self.iface.bounding_box();
+        self.iface.fill_solid(&bb, self.term.bg).await?;
+        self.term.col = 0;
+        self.term.line = 0;
+        Ok(())
+    }
+
+    /// Writes `text` at the cursor.
+    pub async fn write(&mut self, text: &str) -> Result<(), Error<E, DCE, RSTE>> {
+        for c in text.chars() {
+            self.write_char(c).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes formatted text at the cursor, so that `write!(console, ...).await` works.
+    ///
+    /// The text is formatted into a small buffer on the stack and written a chunk at a time,
+    /// formatting `args` again for every chunk of longer text. Formatting errors end the text.
+    pub async fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> Result<(), Error<E, DCE, RSTE>> {
+        let mut buf = [0_u8; FMT_CHUNK];
+        let mut written = 0;
+        loop {
+            let (text, more) = format_chunk(&mut buf, args, written);
+            self.write(text).await?;
+            written += text.len();
+            if !more {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn write_char(&mut self, c: char) -> Result<(), Error<E, DCE, RSTE>> {
+        match self.term.input(c) {
+            Action::None => {}
+            Action::Glyph(c) => {
+                if self.term.col >= self.term.cols {
+                    self.term.col = 0;
+                    self.new_line().await?;
+                }
+                self.draw_glyph(c).await?;
+                self.term.col += 1;
+            }
+            Action::NewLine => self.new_line().await?,
+            Action::ClearCols(from, to) => self.clear_cols(from, to).await?,
+            Action::ClearScreen => {
+                let bb = self.iface.bounding_box();
+                self.iface.fill_solid(&bb, self.term.bg).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the cursor to the next line, scrolling or wrapping around at the last line, and
+    /// clears that line.
+    async fn new_line(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        if self.term.line + 1 < self.term.lines {
+            self.term.line += 1;
+        } else if self.hardware_scroll {
+            self.iface
+                .scroll_screen(self.font.height as u16 + 1)
+                .await?;
+        } else {
+            self.term.line = 0;
+        }
+        self.clear_cols(0, self.term.cols).await
+    }
+
+    /// Clears the cells from column `from` up to `to` of the cursor line.
+    async fn clear_cols(&mut self, from: u16, to: u16) -> Result<(), Error<E, DCE, RSTE>> {
+        if from >= to {
+            return Ok(());
+        }
+        let cell_width = self.font.width as u16 + 1;
+        let rect = self.cell_rect(from, (to - from) * cell_width);
+        self.iface.write_repeated(rect, self.term.bg).await
+    }
+
+    /// Draws `c` in the cell at the cursor.
+    async fn draw_glyph(&mut self, c: char) -> Result<(), Error<E, DCE, RSTE>> {
+        let font = self.font;
+        let glyph = font.glyph(c).or_else(|| font.glyph('?')).unwrap_or(&[]);
+        let rect = self.cell_rect(self.term.col, font.width as u16 + 1);
+        let pixels = glyph_pixels(font, glyph, self.term.fg, self.term.bg);
+        self.iface.write_pixels(rect, pixels).await
+    }
+
+    /// Frame memory area of `width` pixels of the cursor line, starting at column `col`.
+    fn cell_rect(&self, col: u16, width: u16) -> Rect {
+        let (cell_width, cell_height) = (self.font.width as u16 + 1, self.font.height as u16 + 1);
+        let x = col * cell_width;
+        let y = self.term.line * cell_height;
+        // Lines are whole cells of the scroll area, so they never wrap around in frame memory.
+        let (top, bottom) = (
+            self.iface.scrolled(y),
+            self.iface.scrolled(y + cell_height - 1),
+        );
+        Rect::new(x, top.min(bottom), x + width - 1, top.max(bottom))
+    }
+}
+
+/// Pixels of the cell of `glyph` row by row, with a column and a row of spacing.
+fn glyph_pixels<C: Copy>(
+    font: Font,
+    glyph: &'static [u8],
+    fg: C,
+    bg: C,
+) -> impl Iterator<Item = C> {
+    let width = font.width as usize + 1;
+    let rows = (0..font.height).flat_map(move |y| {
+        (0..width).map(move |x| match glyph.get(x) {
+            Some(column) if column >> y & 1 != 0 => fg,
+            _ => bg,
+        })
+    });
+    rows.chain(core::iter::repeat_n(bg, width))
+}
+
+/// Formats `args` into `buf`, leaving out the first `skip` bytes, which must end on a character
+/// boundary.
+///
+/// Returns the text that fits, cut after its last whole character, and whether more follows.
+fn format_chunk<'b>(buf: &'b mut [u8], args: fmt::Arguments<'_>, skip: usize) -> (&'b str, bool) {
+    let mut chunk = Chunk {
+        buf,
+        len: 0,
+        skip,
+        full: false,
+    };
+    // Fails when the chunk is full, or when a formatting trait fails, which ends the text.
+    let _ = fmt::write(&mut chunk, args);
+    let Chunk { buf, len, full, .. } = chunk;
+    let valid = match core::str::from_utf8(&buf[..len]) {
+        Ok(text) => text.len(),
+        Err(error) => error.valid_up_to(),
+    };
+    (core::str::from_utf8(&buf[..valid]).unwrap_or(""), full)
+}
+
+/// [`fmt::Write`] keeping the bytes of formatted text after the first `skip` that fit in `buf`.
+struct Chunk<'b> {
+    buf: &'b mut [u8],
+    len: usize,
+    skip: usize,
+    /// Whether some text did not fit.
+    full: bool,
+}
+
+impl fmt::Write for Chunk<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let skipped = self.skip.min(s.len());
+        self.skip -= skipped;
+        let s = &s.as_bytes()[skipped..];
+        let n = s.len().min(self.buf.len() - self.len);
+        self.buf[self.len..self.len + n].copy_from_slice(&s[..n]);
+        self.len += n;
+        if n < s.len() {
+            self.full = true;
+            return Err(fmt::Error);
+        }
+        Ok(())
+    }
+}
+
+/// Display update needed for a character, returned by [`Terminal::input`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    None,
+    /// Draw the character at the cursor and move right, on a new line if the cursor line is full.
+    Glyph(char),
+    /// Move to the next line and clear it.
+    NewLine,
+    /// Clear the columns `from..to` of the cursor line.
+    ClearCols(u16, u16),
+    /// Clear the screen, keeping the cursor.
+    ClearScreen,
+}
+
+/// Cursor, colors and escape sequence state of a [`Console`], without the display.
+struct Terminal<C> {
+    /// Size of the console in characters.
+    cols: u16,
+    lines: u16,
+    /// Cursor position in characters.
+    col: u16,
+    line: u16,
+    fg: C,
+    bg: C,
+    default_fg: C,
+    default_bg: C,
+    escape: Escape,
+}
+
+impl<C: Copy + From<Rgb888>> Terminal<C> {
+    fn new(cols: u16, lines: u16, fg: C, bg: C) -> Self {
+        Self {
+            cols,
+            lines,
+            col: 0,
+            line: 0,
+            fg,
+            bg,
+            default_fg: fg,
+            default_bg: bg,
+            escape: Escape::None,
+        }
+    }
+
+    /// Takes the next character of the text, returning what to update on the display.
+    fn input(&mut self, c: char) -> Action {
+        match self.escape {
+            Escape::None => {}
+            Escape::Start => {
+                self.escape = if c == '[' {
+                    Escape::Csi {
+                        params: [0; MAX_PARAMS],
+                        len: 0,
+                    }
+                } else {
+                    Escape::None
+                };
+                return Action::None;
+            }
+            Escape::Csi {
+                mut params,
+                mut len,
+            } => {
+                match c {
+                    '0'..='9' => {
+                        len = len.max(1);
+                        if let Some(param) = params.get_mut(len - 1) {
+                            *param = param
+                                .saturating_mul(10)
+                                .saturating_add(c as u16 - '0' as u16);
+                        }
+                        self.escape = Escape::Csi { params, len };
+                    }
+                    ';' => {
+                        self.escape = Escape::Csi {
+                            params,
+                            len: len.max(1) + 1,
+                        }
+                    }
+                    _ => {
+                        self.escape = Escape::None;
+                        let len = len.min(MAX_PARAMS);
+                        return self.control(c, &params[..len]);
+                    }
+                }
+                return Action::None;
+            }
+        }
+        match c {
+            '\x1b' => self.escape = Escape::Start,
+            '\n' => {
+                self.col = 0;
+                return Action::NewLine;
+            }
+            '\r' => self.col = 0,
+            '\t' => self.col = ((self.col / 8 + 1) * 8).min(self.cols),
+            '\x08' => self.col = self.col.saturating_sub(1),
+            c if c.is_control() => {}
+            c => return Action::Glyph(c),
+        }
+        Action::None
+    }
+
+    /// Runs the escape sequence ending in `command` with `params`.
+    fn control(&mut self, command: char, params: &[u16]) -> Action {
+        let param = params.first().copied().unwrap_or(0);
+        match command {
+            'm' => {
+                if params.is_empty() {
+                    self.set_color(0);
+                }
+                for &param in params {
+                    self.set_color(param);
+                }
+            }
+            'K' => {
+                let (from, to) = match param {
+                    0 => (self.col, self.cols),
+                    1 => (0, self.col + 1),
+                    _ => (0, self.cols),
+                };
+                return Action::ClearCols(from, to.min(self.cols));
+            }
+            'J' if param == 2 => return Action::ClearScreen,
+            'H' => {
+                let line = param.max(1) - 1;
+                let col = params.get(1).copied().unwrap_or(0).max(1) - 1;
+                self.line = line.min(self.lines - 1);
+                self.col = col.min(self.cols - 1);
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    /// Applies the SGR color parameter `param`.
+    fn set_color(&mut self, param: u16) {
+        let ansi = |index: u16| C::from(ANSI_COLORS[index as usize]);
+        match param {
+            0 => (self.fg, self.bg) = (self.default_fg, self.default_bg),
+            30..=37 => self.fg = ansi(param - 30),
+            39 => self.fg = self.default_fg,
+            40..=47 => self.bg = ansi(param - 40),
+            49 => self.bg = self.default_bg,
+            90..=97 => self.fg = ansi(param - 90 + 8),
+            100..=107 => self.bg = ansi(param - 100 + 8),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock, model, Config};
+
+    #[test]
+    fn font_covers_printable_ascii() {
+        assert_eq!(
+            FONT_5X7.glyph('~'),
+            Some(&[0x08, 0x04, 0x08, 0x10, 0x08][..])
+        );
+        assert_eq!(FONT_5X7.glyph('\u{7f}'), None);
+        assert_eq!(FONT_5X7.glyph('\n'), None);
+    }
+
+    /// Font of two 8 pixel high columns, the first full and the second with only the ends set.
+    const FONT_2X8: Font = Font {
+        width: 2,
+        height: 8,
+        first: 'a',
+        glyphs: &[0xFF, 0x81],
+    };
+
+    fn terminal() -> Terminal<Rgb565> {
+        Terminal::new(20, 10, Rgb565::WHITE, Rgb565::BLACK)
+    }
+
+    /// Feeds `text` to `term`, returning the last action that updates the display.
+    fn feed(term: &mut Terminal<Rgb565>, text: &str) -> Action {
+        text.chars()
+            .map(|c| term.input(c))
+            .filter(|action| *action != Action::None)
+            .last()
+            .unwrap_or(Action::None)
+    }
+
+    fn ansi(index: usize) -> Rgb565 {
+        ANSI_COLORS[index].into()
+    }
+
+    #[test]
+    fn glyphs_fill_all_eight_rows() {
+        let (fg, bg) = (1_u8, 0_u8);
+        let glyph = FONT_2X8.glyph('a').unwrap();
+        let mut pixels = glyph_pixels(FONT_2X8, glyph, fg, bg);
+        for y in 0..8 {
+            let end = if y == 0 || y == 7 { fg } else { bg };
+            let row = [pixels.next(), pixels.next(), pixels.next()];
+            assert_eq!(row, [Some(fg), Some(end), Some(bg)], "row {}", y);
+        }
+        assert!(pixels.by_ref().take(3).all(|pixel| pixel == bg));
+        assert_eq!(pixels.next(), None);
+    }
+
+    #[test]
+    fn sgr_sets_and_resets_colors() {
+        let mut term = terminal();
+        assert_eq!(feed(&mut term, "\x1b[31;102m"), Action::None);
+        assert_eq!((term.fg, term.bg), (ansi(1), ansi(10)));
+        feed(&mut term, "\x1b[94;40m");
+        assert_eq!((term.fg, term.bg), (ansi(12), ansi(0)));
+        feed(&mut term, "\x1b[39m");
+        assert_eq!((term.fg, term.bg), (Rgb565::WHITE, ansi(0)));
+        feed(&mut term, "\x1b[32m\x1b[49m");
+        assert_eq!((term.fg, term.bg), (ansi(2), Rgb565::BLACK));
+        feed(&mut term, "\x1b[33;44m\x1b[m");
+        assert_eq!((term.fg, term.bg), (Rgb565::WHITE, Rgb565::BLACK));
+        feed(&mut term, "\x1b[35;46m\x1b[0m");
+        assert_eq!((term.fg, term.bg), (Rgb565::WHITE, Rgb565::BLACK));
+    }
+
+    #[test]
+    fn erase_in_line_clears_around_the_cursor() {
+        let mut term = terminal();
+        term.col = 5;
+        assert_eq!(feed(&mut term, "\x1b[K"), Action::ClearCols(5, 20));
+        assert_eq!(feed(&mut term, "\x1b[0K"), Action::ClearCols(5, 20));
+        assert_eq!(feed(&mut term, "\x1b[1K"), Action::ClearCols(0, 6));
+        assert_eq!(feed(&mut term, "\x1b[2K"), Action::ClearCols(0, 20));
+        assert_eq!(term.col, 5);
+    }
+
+    #[test]
+    fn only_erase_in_display_2_clears_the_screen() {
+        let mut term = terminal();
+        (term.col, term.line) = (3, 4);
+        assert_eq!(feed(&mut term, "\x1b[2J"), Action::ClearScreen);
+        assert_eq!((term.col, term.line), (3, 4));
+        assert_eq!(feed(&mut term, "\x1b[J"), Action::None);
+    }
+
+    #[test]
+    fn cursor_position_is_one_based_and_clamped() {
+        let mut term = terminal();
+        feed(&mut term, "\x1b[3;7H");
+        assert_eq!((term.line, term.col), (2, 6));
+        feed(&mut term, "\x1b[H");
+        assert_eq!((term.line, term.col), (0, 0));
+        feed(&mut term, "\x1b[5H");
+        assert_eq!((term.line, term.col), (4, 0));
+        feed(&mut term, "\x1b[99;99H");
+        assert_eq!((term.line, term.col), (9, 19));
+    }
+
+    #[test]
+    fn formatted_text_is_cut_between_characters() {
+        let args = format_args!("{}é{}", "ab", 12345);
+        let mut text = [0_u8; 16];
+        let mut written = 0;
+        let mut chunks = 0;
+        loop {
+            // Too short for "abé1", so "é" moves to the second chunk.
+            let mut buf = [0_u8; 4];
+            let (chunk, more) = format_chunk(&mut buf, args, written);
+            text[written..written + chunk.len()].copy_from_slice(chunk.as_bytes());
+            written += chunk.len();
+            chunks += 1;
+            if !more {
+                break;
+            }
+        }
+        assert_eq!(&text[..written], "abé12345".as_bytes());
+        assert_eq!(chunks, 3);
+    }
+
+    fn console_iface(width: u16, height: u16, log: &mock::Log) -> MockIface<'_> {
+        let config = Config::builder(model::ST7735)
+            .size(width, height)
+            .build()
+            .unwrap();
+        let iface = ST7735IF::new(mock::Spi(log), mock::Dc(log), mock::Rst, config);
+        mock::block_on(iface.init(&mut mock::Delay)).unwrap()
+    }
+
+    type MockIface<'a> = ST7735IF<mock::Spi<'a>, mock::Dc<'a>, mock::Rst, model::ST7735>;
+
+    #[test]
+    fn write_macro_runs_escape_sequences() {
+        let log = mock::Log::new();
+        let mut iface = console_iface(16, 24, &log);
+        let mut console = mock::block_on(Console::new(
+            &mut iface,
+            FONT_5X7,
+            Rgb565::WHITE,
+            Rgb565::BLACK,
+        ))
+        .unwrap();
+        mock::block_on(async { write!(console, "\x1b[{};{}m", 31, 102).await }).unwrap();
+        assert_eq!((console.term.fg, console.term.bg), (ansi(1), ansi(10)));
+        mock::block_on(async { write!(console, "\x1b[{}Hx", 2).await }).unwrap();
+        assert_eq!((console.term.line, console.term.col), (1, 1));
+    }
+
+    #[test]
+    fn console_needs_room_for_a_character() {
+        let log = mock::Log::new();
+        let mut iface = console_iface(5, 7, &log);
+        let console = Console::new(&mut iface, FONT_5X7, Rgb565::WHITE, Rgb565::BLACK);
+        assert!(matches!(
+            mock::block_on(console),
+            Err(Error::InvalidGeometry)
+        ));
+    }
+
+    #[test]
+    fn parameters_past_the_limit_are_ignored() {
+        let mut term = terminal();
+        assert_eq!(feed(&mut term, "\x1b[31;1;1;1;32;42m"), Action::None);
+        assert_eq!((term.fg, term.bg), (ansi(1), Rgb565::BLACK));
+        assert_eq!(feed(&mut term, "x"), Action::Glyph('x'));
+    }
+
+    #[test]
+    fn control_characters_move_the_cursor() {
+        let mut term = terminal();
+        assert_eq!(feed(&mut term, "ab"), Action::Glyph('b'));
+        term.col = 3;
+        feed(&mut term, "\t");
+        assert_eq!(term.col, 8);
+        feed(&mut term, "\x08");
+        assert_eq!(term.col, 7);
+        feed(&mut term, "\r");
+        assert_eq!(term.col, 0);
+        term.col = 4;
+        assert_eq!(feed(&mut term, "\n"), Action::NewLine);
+        assert_eq!(term.col, 0);
+        assert_eq!(feed(&mut term, "\x1bX"), Action::None);
+    }
+}
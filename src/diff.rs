@@ -0,0 +1,170 @@
+//! Change detection between consecutive [`Frame`]s.
+
+use crate::dirty::{DirtyRegions, Rect};
+use crate::{DisplayColor, Frame};
+
+/// Per-tile hashes of the last frame sent with
+/// [`ST7735IF::flush_frame_diff`](crate::ST7735IF::flush_frame_diff).
+///
+/// Frames are split into tiles of `tile_width` x `tile_height` pixels, `T` is the maximum number
+/// of tiles. Only the tiles whose hash changed are sent, unless the display content is unknown or
+/// more than [`full_flush_percent`](Self::full_flush_percent) of the tiles changed, in which case
+/// the whole frame is sent.
+///
+/// Hashes only need a few bytes per tile instead of a copy of the frame, but a changed tile that
+/// happens to hash like the old one is not sent until it changes again.
+pub struct FrameDiff<const T: usize> {
+    tile_width: u16,
+    tile_height: u16,
+    full_flush_percent: u8,
+    hashes: [u32; T],
+    /// Size of the frame the hashes were taken from, `None` if the display content is unknown.
+    size: Option<(u32, u32)>,
+}
+
+impl<const T: usize> FrameDiff<T> {
+    /// Creates a diff with tiles of `tile_width` x `tile_height` pixels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a tile dimension is zero.
+    pub const fn new(tile_width: u16, tile_height: u16) -> Self {
+        assert!(
+            tile_width > 0 && tile_height > 0,
+            "tile size must not be zero"
+        );
+        Self {
+            tile_width,
+            tile_height,
+            full_flush_percent: 50,
+            hashes: [0; T],
+            size: None,
+        }
+    }
+
+    /// Sets the share of changed tiles, in percent, above which the whole frame is sent.
+    ///
+    /// Defaults to 50.
+    pub const fn full_flush_percent(mut self, percent: u8) -> Self {
+        self.full_flush_percent = percent;
+        self
+    }
+
+    /// Forgets the last frame, so the next flush sends the whole frame.
+    pub fn invalidate(&mut self) {
+        self.size = None;
+    }
+
+    /// Hashes the tiles of `frame`, adds the changed ones to `dirty` and remembers the new hashes.
+    ///
+    /// Returns whether the whole frame should be sent instead, or `None` without changing
+    /// anything if `frame` has more than `T` tiles or its buffer is smaller than its size.
+    pub(crate) fn update<const N: usize, C: DisplayColor>(
+        &mut self,
+        frame: &Frame<N, C>,
+        dirty: &mut DirtyRegions,
+    ) -> Option<bool> {
+        let (width, height) = (frame.width, frame.height);
+        let (tile_width, tile_height) = (self.tile_width as u32, self.tile_height as u32);
+        let cols = width / tile_width + (width % tile_width > 0) as u32;
+        let rows = height / tile_height + (height % tile_height > 0) as u32;
+        if (cols * rows) as usize > T || frame.buffer.len() < C::bytes((width * height) as usize) {
+            return None;
+        }
+
+        let known = self.size == Some((width, height));
+        let stride = width as usize;
+        let mut changed = 0;
+        for row in 0..rows {
+            for col in 0..cols {
+                let rect = Rect::new(
+                    (col * tile_width) as u16,
+                    (row * tile_height) as u16,
+                    (((col + 1) * tile_width).min(width) - 1) as u16,
+                    (((row + 1) * tile_height).min(height) - 1) as u16,
+                );
+                let hash = (rect.y0 as usize..=rect.y1 as usize).fold(FNV_OFFSET, |hash, y| {
+                    let start = (y * stride + rect.x0 as usize) * C::BITS / 8;
+                    let end = C::bytes(y * stride + rect.x1 as usize + 1);
+                    fnv1a(hash, &frame.buffer[start..end])
+                });
+                let tile = &mut self.hashes[(row * cols + col) as usize];
+                if !known || *tile != hash {
+                    *tile = hash;
+                    changed += 1;
+                    dirty.add(rect);
+                }
+            }
+        }
+        self.size = Some((width, height));
+
+        Some(!known || changed * 100 > cols * rows * self.full_flush_percent as u32)
+    }
+}
+
+const FNV_OFFSET: u32 = 0x811c_9dc5;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+/// Continues an FNV-1a hash over `bytes`.
+fn fnv1a(hash: u32, bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(hash, |hash, &b| (hash ^ b as u32).wrapping_mul(FNV_PRIME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Orientation;
+
+    fn update(diff: &mut FrameDiff<16>, frame: &Frame<{ 16 * 12 * 2 }>) -> (bool, DirtyRegions) {
+        let mut dirty = DirtyRegions::new();
+        let full = diff.update(frame, &mut dirty).unwrap();
+        (full, dirty)
+    }
+
+    #[test]
+    fn sends_only_changed_tiles() {
+        let mut frame = Frame::new(16, 12, Orientation::Landscape, [0; 16 * 12 * 2]);
+        let mut diff = FrameDiff::<16>::new(4, 4);
+
+        assert!(update(&mut diff, &frame).0);
+        let (full, dirty) = update(&mut diff, &frame);
+        assert!(!full);
+        assert_eq!(dirty.iter().count(), 0);
+
+        frame.buffer[(5 * 16 + 9) * 2] = 0xFF;
+        let (full, dirty) = update(&mut diff, &frame);
+        assert!(!full);
+        assert_eq!(dirty.iter().count(), 1);
+        assert_eq!(dirty.iter().next(), Some(&Rect::new(8, 4, 11, 7)));
+    }
+
+    #[test]
+    fn falls_back_to_full_flush() {
+        let mut frame = Frame::new(16, 12, Orientation::Landscape, [0; 16 * 12 * 2]);
+        let mut diff = FrameDiff::<16>::new(4, 4);
+        update(&mut diff, &frame);
+
+        frame.buffer[..16 * 8 * 2].fill(0xFF);
+        assert!(update(&mut diff, &frame).0);
+
+        diff.invalidate();
+        assert!(update(&mut diff, &frame).0);
+    }
+
+    #[test]
+    fn rejects_frames_it_cannot_track() {
+        let frame: Frame<{ 16 * 12 * 2 }> =
+            Frame::new(16, 12, Orientation::Landscape, [0; 16 * 12 * 2]);
+        let mut dirty = DirtyRegions::new();
+        let mut diff = FrameDiff::<11>::new(4, 4);
+        assert_eq!(diff.update(&frame, &mut dirty), None);
+
+        let frame: Frame<{ 16 * 12 * 2 }> =
+            Frame::new(16, 13, Orientation::Landscape, [0; 16 * 12 * 2]);
+        let mut diff = FrameDiff::<20>::new(4, 4);
+        assert_eq!(diff.update(&frame, &mut dirty), None);
+        assert_eq!(dirty.iter().count(), 0);
+    }
+}
@@ -0,0 +1,476 @@
+//! Drawing straight to the display, without a framebuffer.
+
+use core::convert::Infallible;
+
+use embedded_graphics_core::{prelude::*, primitives::Rectangle};
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal_async::spi::{SpiBus, SpiBusWrite, SpiDevice};
+
+use crate::dirty::Rect;
+use crate::error::comm;
+use crate::instruction::Instruction;
+use crate::{DisplayColor, Error, Model, Op, ST7735IF};
+
+/// Size of the line buffer used to stream pixels, in bytes.
+pub(crate) const LINE_BUFFER: usize = 64;
+
+impl<SPI, DC, RST, M, E, DCE, RSTE> ST7735IF<SPI, DC, RST, M>
+where
+    SPI: SpiDevice<Error = E>,
+    SPI::Bus: SpiBus,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    M: Model,
+{
+    /// Returns a [`DrawTarget`] for `area` of the display, collecting what is drawn into `pixels`
+    /// until [`Direct::flush`] sends it.
+    ///
+    /// Only the part of `area` on the display is kept. Returns `None` if `pixels` holds fewer
+    /// pixels than that part.
+    pub fn direct<'a, C: DisplayColor>(
+        &'a mut self,
+        area: &Rectangle,
+        pixels: &'a mut [Option<C>],
+    ) -> Option<Direct<'a, SPI, DC, RST, M, C>> {
+        let area = area.intersection(&self.bounding_box());
+        let len = (area.size.width * area.size.height) as usize;
+        let pixels = pixels.get_mut(..len)?;
+        pixels.fill(None);
+        Some(Direct {
+            iface: self,
+            area,
+            pixels,
+        })
+    }
+
+    /// Draws `drawable` straight to the display, as many rows of its bounding box at a time as
+    /// fit in `pixels`.
+    ///
+    /// `drawable` is drawn once per band of rows through [`Direct`], so only the pixels it draws
+    /// are sent. Fails with [`Error::InvalidGeometry`] if `pixels` doesn't hold one row of the
+    /// bounding box.
+    pub async fn draw<D, C>(
+        &mut self,
+        drawable: &D,
+        pixels: &mut [Option<C>],
+    ) -> Result<(), Error<E, DCE, RSTE>>
+    where
+        D: Drawable<Color = C> + Dimensions,
+        C: DisplayColor,
+    {
+        let area = drawable.bounding_box().intersection(&self.bounding_box());
+        if area.is_zero_sized() {
+            return Ok(());
+        }
+        let rows = pixels.len() as u32 / area.size.width;
+        if rows == 0 {
+            return Err(Error::InvalidGeometry);
+        }
+        let mut y = 0;
+        while y < area.size.height {
+            let band = Rectangle::new(
+                area.top_left + Point::new(0, y as i32),
+                Size::new(area.size.width, rows.min(area.size.height - y)),
+            );
+            let mut direct = self.direct(&band, pixels).ok_or(Error::InvalidGeometry)?;
+            if let Err(e) = drawable.draw(&mut direct) {
+                match e {}
+            }
+            direct.flush().await?;
+            y += rows;
+        }
+        Ok(())
+    }
+
+    /// Fills `area` with `color`.
+    pub async fn fill_solid<C: DisplayColor>(
+        &mut self,
+        area: &Rectangle,
+        color: C,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        match Rect::from_area(&area.intersection(&self.bounding_box())) {
+            Some(rect) => self.write_repeated(rect, color).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Fills `area` with `colors`, given row by row.
+    ///
+    /// Areas fully on the display are sent in a single burst, others pixel run by pixel run.
+    pub async fn fill_contiguous<C, I>(
+        &mut self,
+        area: &Rectangle,
+        colors: I,
+    ) -> Result<(), Error<E, DCE, RSTE>>
+    where
+        C: DisplayColor,
+        I: IntoIterator<Item = C>,
+    {
+        if area.intersection(&self.bounding_box()) != *area {
+            let pixels = area.points().zip(colors).map(|(p, c)| Pixel(p, c));
+            return self.draw_iter(pixels).await;
+        }
+        match Rect::from_area(area) {
+            Some(rect) => self.write_pixels(rect, colors).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Draws `pixels`, sending each horizontal run of adjacent pixels in one burst.
+    pub async fn draw_iter<C, I>(&mut self, pixels: I) -> Result<(), Error<E, DCE, RSTE>>
+    where
+        C: DisplayColor,
+        I: IntoIterator<Item = Pixel<C>>,
+    {
+        self.select_color::<C>().await?;
+        let bb = self.bounding_box();
+        let capacity = line_capacity::<C>();
+        let mut run = [0_u8; LINE_BUFFER];
+        let mut len = 0;
+        let mut start = (0, 0);
+        for Pixel(pos, color) in pixels.into_iter().filter(|Pixel(pos, _)| bb.contains(*pos)) {
+            let (x, y) = (pos.x as u16, pos.y as u16);
+            if len > 0 && (y != start.1 || x != start.0 + len as u16 || len == capacity) {
+                self.write_run(start, len, &run[..C::bytes(len)]).await?;
+                len = 0;
+            }
+            if len == 0 {
+                start = (x, y);
+            }
+            color.store(&mut run, len);
+            len += 1;
+        }
+        if len > 0 {
+            self.write_run(start, len, &run[..C::bytes(len)]).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes a horizontal run of `len` pixels starting at `start`.
+    async fn write_run(
+        &mut self,
+        start: (u16, u16),
+        len: usize,
+        data: &[u8],
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        let end = start.0 + len as u16 - 1;
+        self.set_address_window(start.0, start.1, end, start.1)
+            .await?;
+        self.write_command(Instruction::RAMWR, &[]).await?;
+        self.start_data()?;
+        self.spi.write(data).await.map_err(comm(Op::MemoryWrite))
+    }
+
+    /// Fills `rect` with `color` in one transaction, sending the same line buffer repeatedly.
+    pub(crate) async fn write_repeated<C: DisplayColor>(
+        &mut self,
+        rect: Rect,
+        color: C,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        self.select_color::<C>().await?;
+        self.set_address_window(rect.x0, rect.y0, rect.x1, rect.y1)
+            .await?;
+        self.write_command(Instruction::RAMWR, &[]).await?;
+        self.start_data()?;
+        let mut remaining = C::bytes(rect.area() as usize);
+        self.spi
+            .transaction(move |bus| async move {
+                let bus = unsafe { &mut *bus };
+                let capacity = line_capacity::<C>();
+                let mut buf = [0_u8; LINE_BUFFER];
+                for i in 0..capacity {
+                    color.store(&mut buf, i);
+                }
+                let chunk = C::bytes(capacity);
+                while remaining > 0 {
+                    let len = remaining.min(chunk);
+                    bus.write(&buf[..len]).await?;
+                    remaining -= len;
+                }
+                Ok(())
+            })
+            .await
+            .map_err(comm(Op::MemoryWrite))
+    }
+
+    /// Fills `rect` with `colors` in one transaction, streamed through a line buffer.
+    pub(crate) async fn write_pixels<C, I>(
+        &mut self,
+        rect: Rect,
+        colors: I,
+    ) -> Result<(), Error<E, DCE, RSTE>>
+    where
+        C: DisplayColor,
+        I: IntoIterator<Item = C>,
+    {
+        self.select_color::<C>().await?;
+        self.set_address_window(rect.x0, rect.y0, rect.x1, rect.y1)
+            .await?;
+        self.write_command(Instruction::RAMWR, &[]).await?;
+        self.start_data()?;
+        let mut colors = colors.into_iter().take(rect.area() as usize);
+        self.spi
+            .transaction(move |bus| async move {
+                let bus = unsafe { &mut *bus };
+                let capacity = line_capacity::<C>();
+                let mut buf = [0_u8; LINE_BUFFER];
+                loop {
+                    let mut len = 0;
+                    for color in (&mut colors).take(capacity) {
+                        color.store(&mut buf, len);
+                        len += 1;
+                    }
+                    if len == 0 {
+                        return Ok(());
+                    }
+                    bus.write(&buf[..C::bytes(len)]).await?;
+                }
+            })
+            .await
+            .map_err(comm(Op::MemoryWrite))
+    }
+}
+
+/// Number of pixels of `C` that fit in the line buffer, keeping whole bytes.
+pub(crate) fn line_capacity<C: DisplayColor>() -> usize {
+    LINE_BUFFER * 8 / C::BITS / C::ALIGN * C::ALIGN
+}
+
+impl<SPI, DC, RST, M, E, DCE, RSTE> OriginDimensions for ST7735IF<SPI, DC, RST, M>
+where
+    SPI: SpiDevice<Error = E>,
+    SPI::Bus: SpiBus,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    M: Model,
+{
+    /// Visible size in the current orientation.
+    fn size(&self) -> Size {
+        let (width, height) = if self.orientation.swap_axes() {
+            (self.size.1, self.size.0)
+        } else {
+            self.size
+        };
+        Size::new(width as u32, height as u32)
+    }
+}
+
+/// Draw target for an area of the display that collects pixels in a caller-provided buffer,
+/// created with [`ST7735IF::direct`].
+///
+/// `DrawTarget` is not async, so drawing only stores the pixels, [`flush`](Self::flush) sends
+/// them. Pixels outside of the area are dropped, pixels that were not drawn keep what the display
+/// shows. [`ST7735IF::draw`] takes care of this for drawables larger than the buffer.
+pub struct Direct<'a, SPI, DC, RST, M, C>
+where
+    SPI: SpiDevice,
+    SPI::Bus: SpiBus,
+    DC: OutputPin,
+    RST: OutputPin,
+    M: Model,
+    C: DisplayColor,
+{
+    iface: &'a mut ST7735IF<SPI, DC, RST, M>,
+    area: Rectangle,
+    /// Pixels of `area` row by row, `None` where nothing was drawn.
+    pixels: &'a mut [Option<C>],
+}
+
+impl<SPI, DC, RST, M, C, E, DCE, RSTE> Direct<'_, SPI, DC, RST, M, C>
+where
+    SPI: SpiDevice<Error = E>,
+    SPI::Bus: SpiBus,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    M: Model,
+    C: DisplayColor,
+{
+    /// Sends the pixels drawn since the last flush and forgets them.
+    ///
+    /// A fully drawn area is sent in one burst, otherwise each horizontal run of drawn pixels is.
+    pub async fn flush(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        let Direct {
+            iface,
+            area,
+            pixels,
+        } = self;
+        let result = if pixels.iter().all(Option::is_some) {
+            iface
+                .fill_contiguous(area, pixels.iter().flatten().copied())
+                .await
+        } else {
+            let drawn = area
+                .points()
+                .zip(pixels.iter())
+                .filter_map(|(pos, color)| color.map(|color| Pixel(pos, color)));
+            iface.draw_iter(drawn).await
+        };
+        pixels.fill(None);
+        result
+    }
+}
+
+impl<SPI, DC, RST, M, C> DrawTarget for Direct<'_, SPI, DC, RST, M, C>
+where
+    SPI: SpiDevice,
+    SPI::Bus: SpiBus,
+    DC: OutputPin,
+    RST: OutputPin,
+    M: Model,
+    C: DisplayColor,
+{
+    type Error = Infallible;
+    type Color = C;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let width = self.area.size.width as usize;
+        for Pixel(pos, color) in pixels {
+            if self.area.contains(pos) {
+                let Point { x, y } = pos - self.area.top_left;
+                self.pixels[y as usize * width + x as usize] = Some(color);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<SPI, DC, RST, M, C> Dimensions for Direct<'_, SPI, DC, RST, M, C>
+where
+    SPI: SpiDevice,
+    SPI::Bus: SpiBus,
+    DC: OutputPin,
+    RST: OutputPin,
+    M: Model,
+    C: DisplayColor,
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.area
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+    use crate::ST7735IF;
+    use embedded_graphics_core::pixelcolor::Rgb565;
+
+    /// Outline of a square, leaving its inside alone.
+    struct Outline(Rectangle);
+
+    impl Drawable for Outline {
+        type Color = Rgb565;
+        type Output = ();
+
+        fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+        where
+            D: DrawTarget<Color = Rgb565>,
+        {
+            let bottom_right = self.0.bottom_right().unwrap();
+            let edge = |p: &Point| {
+                p.x == self.0.top_left.x
+                    || p.y == self.0.top_left.y
+                    || p.x == bottom_right.x
+                    || p.y == bottom_right.y
+            };
+            let pixels = self
+                .0
+                .points()
+                .filter(edge)
+                .map(|p| Pixel(p, Rgb565::WHITE));
+            target.draw_iter(pixels)
+        }
+    }
+
+    impl Dimensions for Outline {
+        fn bounding_box(&self) -> Rectangle {
+            self.0
+        }
+    }
+
+    /// Number of RAMWR commands and of pixel bytes sent after them.
+    fn memory_writes(log: &mock::Log) -> (usize, usize) {
+        log.with(|bytes| {
+            let mut writes = (0, 0);
+            let mut after_ramwr = false;
+            for &(dc, byte) in bytes {
+                if !dc {
+                    after_ramwr = byte == Instruction::RAMWR as u8;
+                    writes.0 += after_ramwr as usize;
+                } else if after_ramwr {
+                    writes.1 += 1;
+                }
+            }
+            writes
+        })
+    }
+
+    #[test]
+    fn draw_sends_only_drawn_pixels() {
+        let log = mock::Log::new();
+        let iface = ST7735IF::new(
+            mock::Spi(&log),
+            mock::Dc(&log),
+            mock::Rst,
+            Default::default(),
+        );
+        let mut iface = mock::block_on(iface.init(&mut mock::Delay)).unwrap();
+        let square = Rectangle::new(Point::new(10, 20), Size::new(3, 3));
+        let mut pixels = [None; 4];
+
+        log.clear();
+        mock::block_on(iface.draw(&Outline(square), &mut pixels)).unwrap();
+        // One run each for the top and bottom rows, two single pixels for the middle row.
+        assert_eq!(memory_writes(&log), (4, 8 * 2));
+
+        log.clear();
+        let frame = Outline(Rectangle::new(Point::new(10, 20), Size::new(2, 2)));
+        mock::block_on(iface.draw(&frame, &mut pixels)).unwrap();
+        // Fully drawn, so one burst.
+        assert_eq!(memory_writes(&log), (1, 4 * 2));
+
+        let mut short = [None; 2];
+        assert!(matches!(
+            mock::block_on(iface.draw(&Outline(square), &mut short)),
+            Err(Error::InvalidGeometry)
+        ));
+    }
+
+    #[test]
+    fn direct_keeps_pixels_of_its_area() {
+        let log = mock::Log::new();
+        let iface = ST7735IF::new(
+            mock::Spi(&log),
+            mock::Dc(&log),
+            mock::Rst,
+            Default::default(),
+        );
+        let mut iface = mock::block_on(iface.init(&mut mock::Delay)).unwrap();
+        let mut pixels = [None; 6];
+        let area = Rectangle::new(Point::new(-1, 0), Size::new(3, 2));
+        let mut direct = iface.direct(&area, &mut pixels).unwrap();
+        assert_eq!(
+            direct.bounding_box(),
+            Rectangle::new(Point::zero(), Size::new(2, 2))
+        );
+        direct
+            .draw_iter([
+                Pixel(Point::new(1, 1), Rgb565::RED),
+                Pixel(Point::new(-1, 0), Rgb565::RED),
+                Pixel(Point::new(2, 0), Rgb565::RED),
+            ])
+            .unwrap();
+        assert_eq!(direct.pixels, [None, None, None, Some(Rgb565::RED)]);
+
+        log.clear();
+        mock::block_on(direct.flush()).unwrap();
+        assert_eq!(memory_writes(&log), (1, 2));
+        assert!(direct.pixels.iter().all(Option::is_none));
+
+        let mut short: [Option<Rgb565>; 3] = [None; 3];
+        assert!(iface.direct(&area, &mut short).is_none());
+    }
+}
@@ -0,0 +1,179 @@
+//! Tracking of the framebuffer areas that changed since the last flush.
+
+use embedded_graphics_core::primitives::Rectangle;
+
+/// Maximum number of separate regions tracked before they are merged.
+const MAX_REGIONS: usize = 8;
+
+/// Extra pixels a merged region may cover compared to its parts.
+///
+/// Every region costs CASET, RASET and RAMWR plus their transactions, which takes about as long
+/// as sending this many pixels, so closer regions are cheaper to send as one.
+const MERGE_SLACK: u32 = 64;
+
+/// Rectangle with inclusive corners, in logical pixel coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Rect {
+    pub x0: u16,
+    pub y0: u16,
+    pub x1: u16,
+    pub y1: u16,
+}
+
+impl Rect {
+    pub const fn new(x0: u16, y0: u16, x1: u16, y1: u16) -> Self {
+        Self { x0, y0, x1, y1 }
+    }
+
+    pub const fn point(x: u16, y: u16) -> Self {
+        Self::new(x, y, x, y)
+    }
+
+    /// Pixels covered by `area`, which must not be left of or above the origin, or `None` if it's
+    /// empty.
+    pub fn from_area(area: &Rectangle) -> Option<Self> {
+        area.bottom_right().map(|end| {
+            Self::new(
+                area.top_left.x as u16,
+                area.top_left.y as u16,
+                end.x as u16,
+                end.y as u16,
+            )
+        })
+    }
+
+    pub fn width(&self) -> u16 {
+        self.x1 - self.x0 + 1
+    }
+
+    pub fn height(&self) -> u16 {
+        self.y1 - self.y0 + 1
+    }
+
+    pub fn area(&self) -> u32 {
+        self.width() as u32 * self.height() as u32
+    }
+
+    pub fn contains(&self, other: &Rect) -> bool {
+        self.x0 <= other.x0 && self.y0 <= other.y0 && self.x1 >= other.x1 && self.y1 >= other.y1
+    }
+
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect::new(
+            self.x0.min(other.x0),
+            self.y0.min(other.y0),
+            self.x1.max(other.x1),
+            self.y1.max(other.y1),
+        )
+    }
+
+    /// Whether sending the union of both is cheaper than sending them separately.
+    fn should_merge(&self, other: &Rect) -> bool {
+        self.union(other).area() <= self.area() + other.area() + MERGE_SLACK
+    }
+}
+
+/// Set of dirty regions of a framebuffer.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DirtyRegions {
+    rects: [Rect; MAX_REGIONS],
+    len: usize,
+}
+
+impl DirtyRegions {
+    pub const fn new() -> Self {
+        Self {
+            rects: [Rect::point(0, 0); MAX_REGIONS],
+            len: 0,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Rect> {
+        self.rects[..self.len].iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Marks `rect` as dirty, merging it with nearby regions.
+    pub fn add(&mut self, rect: Rect) {
+        if self.iter().any(|r| r.contains(&rect)) {
+            return;
+        }
+
+        let mut rect = rect;
+        loop {
+            let close = self.iter().position(|r| rect.should_merge(r));
+            match close {
+                Some(i) => rect = rect.union(&self.remove(i)),
+                None if self.len == MAX_REGIONS => {
+                    // Out of space: merge with the region that grows the least.
+                    let i = (0..self.len)
+                        .min_by_key(|&i| {
+                            let r = &self.rects[i];
+                            r.union(&rect).area() - r.area()
+                        })
+                        .unwrap_or(0);
+                    rect = rect.union(&self.remove(i));
+                }
+                None => break,
+            }
+        }
+        self.rects[self.len] = rect;
+        self.len += 1;
+    }
+
+    fn remove(&mut self, i: usize) -> Rect {
+        let rect = self.rects[i];
+        self.len -= 1;
+        self.rects[i] = self.rects[self.len];
+        rect
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adjacent_pixels_grow_one_region() {
+        let mut dirty = DirtyRegions::new();
+        for x in 10..20 {
+            dirty.add(Rect::point(x, 5));
+        }
+        assert_eq!(dirty.iter().count(), 1);
+        assert_eq!(dirty.iter().next(), Some(&Rect::new(10, 5, 19, 5)));
+    }
+
+    #[test]
+    fn distant_regions_stay_separate() {
+        let mut dirty = DirtyRegions::new();
+        dirty.add(Rect::new(0, 0, 9, 9));
+        dirty.add(Rect::new(100, 100, 109, 109));
+        assert_eq!(dirty.iter().count(), 2);
+    }
+
+    #[test]
+    fn bridging_region_merges_neighbours() {
+        let mut dirty = DirtyRegions::new();
+        dirty.add(Rect::new(0, 0, 9, 9));
+        dirty.add(Rect::new(0, 40, 9, 49));
+        dirty.add(Rect::new(0, 10, 9, 39));
+        assert_eq!(dirty.iter().count(), 1);
+        assert_eq!(dirty.iter().next(), Some(&Rect::new(0, 0, 9, 49)));
+    }
+
+    #[test]
+    fn capacity_is_bounded() {
+        let mut dirty = DirtyRegions::new();
+        for i in 0..2 * MAX_REGIONS as u16 {
+            dirty.add(Rect::point(i * 20, i * 20));
+        }
+        assert_eq!(dirty.iter().count(), MAX_REGIONS);
+        for i in 0..2 * MAX_REGIONS as u16 {
+            let p = Rect::point(i * 20, i * 20);
+            assert!(dirty.iter().any(|r| r.contains(&p)));
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Errors of the driver and its draw targets.
+
+use core::convert::Infallible;
+use core::fmt;
+
+use crate::instruction::Instruction;
+
+/// Driver error.
+///
+/// Communication, DC pin and delay errors carry the [`Op`] they happened in. `E` is the error
+/// type of the SPI device, `DCE` and `RSTE` those of the DC and reset pins.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error<E = (), DCE = Infallible, RSTE = Infallible> {
+    /// Communication error
+    Comm(Op, E),
+    /// Error setting the DC pin
+    Dc(Op, DCE),
+    /// Error setting the reset pin, which is only used in [`Op::Reset`]
+    Reset(RSTE),
+    /// The delay failed, so the controller may not have had the time it needs
+    Delay(Op),
+    /// Command not allowed in the current power state
+    InvalidState,
+    /// Window or rows outside of the panel, or in the wrong order
+    InvalidGeometry,
+    /// Configuration the model does not support, e.g. its color mode
+    Unsupported,
+}
+
+impl<E, DCE, RSTE> Error<E, DCE, RSTE> {
+    /// Operation that failed, if the error happened while talking to the controller.
+    pub fn op(&self) -> Option<Op> {
+        match *self {
+            Self::Comm(op, _) | Self::Dc(op, _) | Self::Delay(op) => Some(op),
+            Self::Reset(_) => Some(Op::Reset),
+            _ => None,
+        }
+    }
+
+    /// Attributes the error to `op` instead of the step of `op` it happened in.
+    pub(crate) fn during(self, op: Op) -> Self {
+        match self {
+            Self::Comm(_, e) => Self::Comm(op, e),
+            Self::Dc(_, e) => Self::Dc(op, e),
+            Self::Delay(_) => Self::Delay(op),
+            other => other,
+        }
+    }
+}
+
+/// Error of a lifecycle transition like `init`, `sleep` or `wake`, giving back the driver.
+///
+/// The driver stays in the state it was in, so the transition can be retried or the parts taken
+/// back with `release`. `?` converts it into its [`Error`].
+pub struct StateError<D, E = (), DCE = Infallible, RSTE = Infallible> {
+    /// What went wrong
+    pub error: Error<E, DCE, RSTE>,
+    /// The driver, in the state it was in before the transition
+    pub driver: D,
+}
+
+impl<D, E, DCE, RSTE> StateError<D, E, DCE, RSTE> {
+    /// Replaces the driver with the one built from it by `f`.
+    pub(crate) fn map_driver<T>(self, f: impl FnOnce(D) -> T) -> StateError<T, E, DCE, RSTE> {
+        StateError {
+            error: self.error,
+            driver: f(self.driver),
+        }
+    }
+}
+
+impl<D, E, DCE, RSTE> From<StateError<D, E, DCE, RSTE>> for Error<E, DCE, RSTE> {
+    fn from(e: StateError<D, E, DCE, RSTE>) -> Self {
+        e.error
+    }
+}
+
+impl<D, E: fmt::Debug, DCE: fmt::Debug, RSTE: fmt::Debug> fmt::Debug
+    for StateError<D, E, DCE, RSTE>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateError")
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<D, E, DCE, RSTE> defmt::Format for StateError<D, E, DCE, RSTE>
+where
+    E: defmt::Format,
+    DCE: defmt::Format,
+    RSTE: defmt::Format,
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "StateError {{ error: {} }}", self.error)
+    }
+}
+
+/// Operation an [`Error`] happened in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Op {
+    /// Hardware or software reset
+    Reset,
+    /// Command `n` of the initialization sequence, counting from 0
+    Init(u8),
+    /// Sending a command and its parameters
+    Command(Instruction),
+    /// Setting the address window with CASET and RASET
+    AddressWindow,
+    /// Writing pixels to frame memory after RAMWR
+    MemoryWrite,
+}
+
+/// Maps a communication error to an [`Error`] in `op`, for `map_err`.
+pub(crate) fn comm<E, DCE, RSTE>(op: Op) -> impl FnOnce(E) -> Error<E, DCE, RSTE> {
+    move |e| Error::Comm(op, e)
+}
+
+/// Maps a DC pin error to an [`Error`] in `op`, for `map_err`.
+pub(crate) fn dc<E, DCE, RSTE>(op: Op) -> impl FnOnce(DCE) -> Error<E, DCE, RSTE> {
+    move |e| Error::Dc(op, e)
+}
+
+/// Error of the framebuffer draw targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DrawError {
+    /// The pixel is within the target, but its color can't be stored: the buffer is smaller than
+    /// the target, or a palette index is past the palette
+    OutOfRange,
+}
@@ -0,0 +1,253 @@
+//! Frames storing palette indices instead of colors.
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    pixelcolor::{raw::RawU8, PixelColor, Rgb565},
+    prelude::*,
+    primitives::Rectangle,
+};
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal_async::spi::{SpiBus, SpiBusWrite, SpiDevice};
+
+use crate::direct::{line_capacity, LINE_BUFFER};
+use crate::dirty::Rect;
+use crate::error::comm;
+use crate::instruction::Instruction;
+use crate::{
+    frame_end, pixel_index, DisplayColor, DrawError, Error, Model, Op, Orientation, ST7735IF,
+};
+
+/// Color of an [`IndexedFrame`], the index of an entry of its palette.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub struct PaletteIndex(pub u8);
+
+impl PixelColor for PaletteIndex {
+    type Raw = RawU8;
+}
+
+impl From<RawU8> for PaletteIndex {
+    fn from(raw: RawU8) -> Self {
+        Self(raw.into_inner())
+    }
+}
+
+/// Frame of `width` x `height` palette indices packed in `N` bytes, with a palette of `P` colors.
+///
+/// Pixels take 1, 4 or 8 bits, the smallest that holds `P` colors, so a 160x128 frame needs
+/// 2560 bytes with 2 colors, 10240 with up to 16 and 20480 with up to 256. Pixels are packed
+/// row-major with the first pixel in the upper bits of a byte. Indices are expanded to `C` while
+/// they are sent with [`ST7735IF::flush_indexed_frame`].
+pub struct IndexedFrame<const N: usize, const P: usize, C = Rgb565> {
+    pub width: u32,
+    pub height: u32,
+    pub orientation: Orientation,
+    pub buffer: [u8; N],
+    pub palette: [C; P],
+}
+
+impl<const N: usize, const P: usize, C: DisplayColor> IndexedFrame<N, P, C> {
+    /// Bits per pixel.
+    pub const BITS: usize = if P <= 2 {
+        1
+    } else if P <= 16 {
+        4
+    } else {
+        8
+    };
+
+    /// Creates a frame drawing with the colors of `palette`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the palette is empty or has more than 256 colors.
+    pub fn new(
+        width: u32,
+        height: u32,
+        orientation: Orientation,
+        buffer: [u8; N],
+        palette: [C; P],
+    ) -> Self {
+        assert!(P > 0 && P <= 256, "palette must have 1 to 256 colors");
+        Self {
+            width,
+            height,
+            orientation,
+            buffer,
+            palette,
+        }
+    }
+
+    /// Changes the orientation the frame is drawn in, swapping width and height if needed.
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        (self.width, self.height) = self
+            .orientation
+            .reorient(orientation, (self.width, self.height));
+        self.orientation = orientation;
+    }
+
+    /// Sets a pixel to palette entry `index`, indices past the palette select its last color.
+    pub fn set_pixel(&mut self, x: u16, y: u16, index: PaletteIndex) {
+        if let Some(idx) = pixel_index(self.width, self.height, x, y) {
+            self.store(idx, (index.0 as usize).min(P - 1) as u8);
+        }
+    }
+
+    /// Palette index of pixel `idx`.
+    fn index(&self, idx: usize) -> usize {
+        let per_byte = 8 / Self::BITS;
+        let shift = (per_byte - 1 - idx % per_byte) * Self::BITS;
+        (self.buffer[idx / per_byte] as usize >> shift) & ((1 << Self::BITS) - 1)
+    }
+
+    fn store(&mut self, idx: usize, index: u8) {
+        let per_byte = 8 / Self::BITS;
+        let shift = (per_byte - 1 - idx % per_byte) * Self::BITS;
+        let mask = (((1_u16 << Self::BITS) - 1) << shift) as u8;
+        if let Some(byte) = self.buffer.get_mut(idx / per_byte) {
+            *byte = (*byte & !mask) | (index << shift & mask);
+        }
+    }
+
+    /// Color of pixel `idx`.
+    fn color(&self, idx: usize) -> C {
+        self.palette[self.index(idx).min(P - 1)]
+    }
+
+    /// Fails with [`DrawError::OutOfRange`] if `index` is past the palette or the frame is larger
+    /// than its buffer.
+    fn check(&self, index: PaletteIndex) -> Result<(), DrawError> {
+        let pixels = self.width as usize * self.height as usize;
+        if index.0 as usize >= P || pixels * Self::BITS > N * 8 {
+            return Err(DrawError::OutOfRange);
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize, const P: usize, C: DisplayColor> DrawTarget for IndexedFrame<N, P, C> {
+    /// Pixels outside of the frame are dropped, drawing fails at the first index past the
+    /// palette.
+    type Error = DrawError;
+    type Color = PaletteIndex;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bb = self.bounding_box();
+        for Pixel(pos, color) in pixels {
+            if bb.contains(pos) {
+                self.check(color)?;
+                self.set_pixel(pos.x as u16, pos.y as u16, color);
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.check(color)?;
+        if let Some(rect) = Rect::from_area(&area.intersection(&self.bounding_box())) {
+            for y in rect.y0..=rect.y1 {
+                for x in rect.x0..=rect.x1 {
+                    self.set_pixel(x, y, color);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize, const P: usize, C> OriginDimensions for IndexedFrame<N, P, C> {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
+
+impl<SPI, DC, RST, M, E, DCE, RSTE> ST7735IF<SPI, DC, RST, M>
+where
+    SPI: SpiDevice<Error = E>,
+    SPI::Bus: SpiBus,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    M: Model,
+{
+    /// Sends the whole `frame`, looking up the color of every pixel in its palette.
+    ///
+    /// Colors are expanded into a small line buffer, which is sent whenever it is full.
+    ///
+    /// Fails with [`Error::InvalidGeometry`] if `frame` is empty or its buffer is smaller than its
+    /// size.
+    pub async fn flush_indexed_frame<const N: usize, const P: usize, C: DisplayColor>(
+        &mut self,
+        frame: &IndexedFrame<N, P, C>,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        let pixels = frame.width as usize * frame.height as usize;
+        if pixels * IndexedFrame::<N, P, C>::BITS > N * 8 {
+            return Err(Error::InvalidGeometry);
+        }
+        let (ex, ey) = frame_end(frame.width, frame.height, 1).ok_or(Error::InvalidGeometry)?;
+        self.select_color::<C>().await?;
+        self.set_address_window(0, 0, ex, ey).await?;
+        self.write_command(Instruction::RAMWR, &[]).await?;
+        self.start_data()?;
+        self.spi
+            .transaction(move |bus| async move {
+                let bus = unsafe { &mut *bus };
+                let capacity = line_capacity::<C>();
+                let mut buf = [0_u8; LINE_BUFFER];
+                let mut start = 0;
+                while start < pixels {
+                    let len = capacity.min(pixels - start);
+                    for i in 0..len {
+                        frame.color(start + i).store(&mut buf, i);
+                    }
+                    bus.write(&buf[..C::bytes(len)]).await?;
+                    start += len;
+                }
+                Ok(())
+            })
+            .await
+            .map_err(comm(Op::MemoryWrite))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_indices_by_depth() {
+        let palette = [Rgb565::BLACK, Rgb565::WHITE, Rgb565::RED];
+        let mut frame = IndexedFrame::new(4, 2, Orientation::Landscape, [0; 4], palette);
+        assert_eq!(IndexedFrame::<4, 3>::BITS, 4);
+        frame.set_pixel(1, 0, PaletteIndex(2));
+        frame.set_pixel(2, 1, PaletteIndex(9));
+        assert_eq!(frame.buffer, [0x02, 0x00, 0x00, 0x20]);
+        assert_eq!(frame.color(1), Rgb565::RED);
+        assert_eq!(frame.color(0), Rgb565::BLACK);
+
+        let mut frame = IndexedFrame::new(8, 2, Orientation::Landscape, [0; 2], [Rgb565::BLACK; 2]);
+        frame
+            .fill_solid(
+                &Rectangle::new(Point::new(6, 0), Size::new(4, 2)),
+                PaletteIndex(1),
+            )
+            .unwrap();
+        assert_eq!(frame.buffer, [0b0000_0011, 0b0000_0011]);
+    }
+
+    #[test]
+    fn rejects_indices_past_the_palette() {
+        let mut frame = IndexedFrame::new(4, 2, Orientation::Landscape, [0; 4], [Rgb565::BLACK; 3]);
+        let pixels = [
+            Pixel(Point::new(0, 0), PaletteIndex(2)),
+            Pixel(Point::new(1, 0), PaletteIndex(3)),
+            Pixel(Point::new(2, 0), PaletteIndex(1)),
+        ];
+        assert_eq!(frame.draw_iter(pixels), Err(DrawError::OutOfRange));
+        assert_eq!(frame.buffer, [0x20, 0x00, 0x00, 0x00]);
+        // Pixels outside of the frame are dropped before their index is checked.
+        let outside = Pixel(Point::new(4, 0), PaletteIndex(9));
+        assert_eq!(frame.draw_iter([outside]), Ok(()));
+    }
+}
@@ -1,5 +1,6 @@
 /// ST7735 instructions.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Instruction {
     NOP = 0x00,
     SWRESET = 0x01,
@@ -18,6 +19,10 @@ pub enum Instruction {
     RAMWR = 0x2C,
     RAMRD = 0x2E,
     PTLAR = 0x30,
+    VSCRDEF = 0x33,
+    VSCRSADD = 0x37,
+    IDMOFF = 0x38,
+    IDMON = 0x39,
     COLMOD = 0x3A,
     MADCTL = 0x36,
     FRMCTR1 = 0xB1,
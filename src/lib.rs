@@ -1,49 +1,156 @@
 #![no_std]
 
+mod bands;
+pub mod color;
+pub mod config;
+pub mod console;
+pub mod diff;
+mod direct;
+mod dirty;
+mod error;
+pub mod indexed;
 pub mod instruction;
+#[cfg(test)]
+mod mock;
+pub mod model;
+#[cfg(all(target_has_atomic = "8", target_has_atomic = "32"))]
+mod presenter;
+pub mod state;
+pub use crate::bands::Band;
+pub use crate::color::{DisplayColor, Rgb444};
+pub use crate::config::{ColorOrder, Config, ConfigBuilder, ConfigError};
+pub use crate::console::Console;
+pub use crate::diff::FrameDiff;
+pub use crate::direct::Direct;
+use crate::dirty::{DirtyRegions, Rect};
+use crate::error::{comm, dc};
+pub use crate::error::{DrawError, Error, Op, StateError};
+pub use crate::indexed::{IndexedFrame, PaletteIndex};
 use crate::instruction::Instruction;
+pub use crate::model::Model;
+use crate::model::{ColorMode, Command};
+#[cfg(all(target_has_atomic = "8", target_has_atomic = "32"))]
+pub use crate::presenter::{Canvas, Flusher, Presenter, PresenterStats};
+pub use crate::state::{Ready, Sleeping, Uninitialized};
 use core::convert::Infallible;
+use core::marker::PhantomData;
+use core::ops::RangeInclusive;
 use embedded_hal::digital::v2::OutputPin;
 use embedded_hal_async::delay::DelayUs;
 use embedded_hal_async::spi::{SpiBus, SpiBusWrite, SpiDevice};
 
-/// 128px x 160px screen with 16 bits (2 bytes) per pixel
-const BUF_SIZE: usize = 128 * 160 * 2;
-
 /// Async ST7735 LCD display driver.
-pub struct ST7735IF<SPI, DC, RST>
+///
+/// The controller is selected with the `M` parameter, see [`model`]. `S` is the lifecycle
+/// state, see [`state`]: [`new`](ST7735IF::new) creates an [`Uninitialized`] driver and
+/// [`init`](ST7735IF::init) turns it into a [`Ready`] one.
+pub struct ST7735IF<SPI, DC, RST, M = model::ST7735, S = Ready>
 where
     SPI: SpiDevice,
     SPI::Bus: SpiBus,
-    DC: OutputPin<Error = Infallible>,
-    RST: OutputPin<Error = Infallible>,
+    DC: OutputPin,
+    RST: OutputPin,
+    M: Model,
 {
     /// SPI
     spi: SPI,
     /// Data/command pin.
     dc: DC,
-    /// Reset pin.
-    rst: RST,
+    /// Reset pin, reset with SWRESET without one.
+    rst: Option<RST>,
+    /// Length of the reset pulse in µs and time to wait after a reset in ms
+    reset_timing: (u32, u32),
     /// Whether the display is RGB (true) or BGR (false)
     rgb: bool,
     /// Whether the colours are inverted (true) or not (false)
     inverted: bool,
-    /// Global image offset
+    /// Pixel format sent to the display
+    color_mode: ColorMode,
+    /// Channel order of the pixel data sent to the display
+    color_order: ColorOrder,
+    /// Visible panel size in the native orientation
+    size: (u16, u16),
+    /// Offset of the visible area in frame memory, in the native orientation
+    offset: (u16, u16),
+    /// Column/row offset for the current orientation
     dx: u16,
     dy: u16,
     orientation: Orientation,
+    /// First row and number of rows of the scroll area, in visible rows of the native orientation
+    scroll_area: Option<(u16, u16)>,
+    /// Rows the scroll area is scrolled by
+    scroll_offset: u16,
+    power: PowerState,
+    /// Module revision, selects the initialization sequence.
+    variant: M::Variant,
+    state: PhantomData<S>,
 }
-pub struct ST7735<SPI, DC, RST>
-where
+/// Async ST7735 LCD display driver with a `W` x `H` pixel framebuffer.
+///
+/// `W` and `H` are the size of the display in the orientation it is configured with. The
+/// framebuffer is stored in `BUF`, which can be a [`Framebuffer`] or any caller-provided byte
+/// storage, e.g. a `&'static mut [u8; N]`, so it never has to be moved through the stack.
+///
+/// Pixels are stored and sent as `C`, which must match the color mode of the configuration.
+pub struct ST7735<
+    SPI,
+    DC,
+    RST,
+    const W: usize,
+    const H: usize,
+    BUF = Framebuffer<W, H>,
+    M = model::ST7735,
+    C = Rgb565,
+    S = Ready,
+> where
     SPI: SpiDevice,
     SPI::Bus: SpiBus,
-    DC: OutputPin<Error = Infallible>,
-    RST: OutputPin<Error = Infallible>,
+    DC: OutputPin,
+    RST: OutputPin,
+    BUF: AsMut<[u8]>,
+    M: Model,
+    C: DisplayColor,
 {
-    iface: ST7735IF<SPI, DC, RST>,
+    iface: ST7735IF<SPI, DC, RST, M, S>,
     width: u32,
     height: u32,
-    buffer: [u8; BUF_SIZE],
+    /// Pixels packed as `C`
+    buffer: BUF,
+    /// Areas of the framebuffer changed since the last flush
+    dirty: DirtyRegions,
+    color: PhantomData<C>,
+}
+
+/// Owned framebuffer of `W` x `H` pixels of `B` bytes.
+///
+/// The default of 2 bytes fits RGB565 and RGB444, RGB666 needs 3.
+pub struct Framebuffer<const W: usize, const H: usize, const B: usize = 2>([[[u8; B]; W]; H]);
+
+impl<const W: usize, const H: usize, const B: usize> Framebuffer<W, H, B> {
+    pub const fn new() -> Self {
+        Self([[[0; B]; W]; H])
+    }
+}
+
+impl<const W: usize, const H: usize, const B: usize> Default for Framebuffer<W, H, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const W: usize, const H: usize, const B: usize> AsRef<[u8]> for Framebuffer<W, H, B> {
+    fn as_ref(&self) -> &[u8] {
+        // SAFETY: nested byte arrays have no padding, so the buffer is `W * H * B` initialized
+        // bytes.
+        unsafe { core::slice::from_raw_parts(self.0.as_ptr() as *const u8, W * H * B) }
+    }
+}
+
+impl<const W: usize, const H: usize, const B: usize> AsMut<[u8]> for Framebuffer<W, H, B> {
+    fn as_mut(&mut self) -> &mut [u8] {
+        // SAFETY: see `as_ref`.
+        unsafe { core::slice::from_raw_parts_mut(self.0.as_mut_ptr() as *mut u8, W * H * B) }
+    }
 }
 
 /// Display orientation.
@@ -55,208 +162,549 @@ pub enum Orientation {
     LandscapeSwapped = 0xA0,
 }
 
-pub struct Config {
-    rgb: bool,
-    inverted: bool,
-    orientation: Orientation,
-}
+impl Orientation {
+    /// Whether rows and columns are exchanged relative to the native orientation.
+    pub(crate) fn swap_axes(self) -> bool {
+        self as u8 & 0x20 != 0
+    }
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            rgb: true,
-            inverted: false,
-            orientation: Orientation::Landscape,
+    /// Whether the column address order is reversed.
+    pub(crate) fn mirror_x(self) -> bool {
+        self as u8 & 0x40 != 0
+    }
+
+    /// Whether the row address order is reversed.
+    pub(crate) fn mirror_y(self) -> bool {
+        self as u8 & 0x80 != 0
+    }
+
+    /// Size of an area of `size` in this orientation, seen in orientation `to`.
+    pub(crate) fn reorient(self, to: Orientation, size: (u32, u32)) -> (u32, u32) {
+        if self.swap_axes() != to.swap_axes() {
+            (size.1, size.0)
+        } else {
+            size
+        }
+    }
+
+    /// Column/row address offset of a panel of `size` placed at `offset` in a frame memory of
+    /// `ram`, all given in the native orientation.
+    ///
+    /// Mirroring an axis moves the visible area to the other end of that axis, exchanging the
+    /// axes swaps the column and row offsets.
+    pub(crate) fn ram_offset(
+        self,
+        ram: (u16, u16),
+        size: (u16, u16),
+        offset: (u16, u16),
+    ) -> (u16, u16) {
+        let col = if self.mirror_x() {
+            ram.0.saturating_sub(size.0 + offset.0)
+        } else {
+            offset.0
+        };
+        let row = if self.mirror_y() {
+            ram.1.saturating_sub(size.1 + offset.1)
+        } else {
+            offset.1
+        };
+        if self.swap_axes() {
+            (row, col)
+        } else {
+            (col, row)
         }
     }
 }
 
-impl<SPI, DC, RST, E> ST7735IF<SPI, DC, RST>
+impl<SPI, DC, RST, M, E, DCE, RSTE> ST7735IF<SPI, DC, RST, M, Uninitialized>
 where
     SPI: SpiDevice<Error = E>,
     SPI::Bus: SpiBus,
-    DC: OutputPin<Error = Infallible>,
-    RST: OutputPin<Error = Infallible>,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    M: Model,
 {
     /// Creates a new driver instance that uses hardware SPI.
-    pub fn new(spi: SPI, dc: DC, rst: RST, config: Config) -> Self {
+    pub fn new(spi: SPI, dc: DC, rst: RST, config: Config<M>) -> Self {
+        Self::with_reset_pin(spi, dc, Some(rst), config)
+    }
+
+    fn with_reset_pin(spi: SPI, dc: DC, rst: Option<RST>, config: Config<M>) -> Self {
+        let (dx, dy) =
+            config
+                .orientation
+                .ram_offset(M::ram_size(config.variant), config.size, config.offset);
         Self {
             spi,
             dc,
             rst,
+            reset_timing: config.reset_timing,
             rgb: config.rgb,
             inverted: config.inverted,
             orientation: config.orientation,
-            dx: 0,
-            dy: 0,
+            color_mode: config.color_mode,
+            color_order: ColorOrder::Rgb,
+            size: config.size,
+            offset: config.offset,
+            dx,
+            dy,
+            scroll_area: None,
+            scroll_offset: 0,
+            power: PowerState::RESET,
+            variant: config.variant,
+            state: PhantomData,
         }
     }
 
-    /// Runs commands to initialize the display.
-    pub async fn init<D>(&mut self, delay: &mut D) -> Result<(), Error<E>>
+    /// Runs commands to initialize the display, returning a driver ready to draw.
+    ///
+    /// On failure the uninitialized driver is given back with the error.
+    pub async fn init<D>(
+        mut self,
+        delay: &mut D,
+    ) -> Result<ST7735IF<SPI, DC, RST, M>, StateError<Self, E, DCE, RSTE>>
     where
         D: DelayUs,
     {
-        self.hard_reset(delay).await?;
-        let dc = &mut self.dc;
-        let inverted = self.inverted;
-        let rgb = self.rgb;
-
-        struct Command<'a> {
-            instruction: Instruction,
-            params: &'a [u8],
-            delay_time: u32,
+        if let Err(error) = self.run_init(delay).await {
+            return Err(StateError {
+                error,
+                driver: self,
+            });
         }
-
-        impl<'a> Command<'a> {
-            fn new(instruction: Instruction, params: &'a [u8], delay_time: u32) -> Self {
-                Self {
-                    instruction,
-                    params,
-                    delay_time,
-                }
-            }
+        let mut ready = self.into_state();
+        match ready.set_orientation(ready.orientation).await {
+            Ok(()) => Ok(ready),
+            Err(error) => Err(StateError {
+                error,
+                driver: ready.into_state(),
+            }),
         }
+    }
+
+    /// Resets the controller and sends the initialization sequence.
+    async fn run_init<D>(&mut self, delay: &mut D) -> Result<(), Error<E, DCE, RSTE>>
+    where
+        D: DelayUs,
+    {
+        self.reset(delay).await?;
+        let dc = &mut self.dc;
+        let init_sequence = M::init_sequence(self.variant);
+        let colmod = [M::colmod(self.color_mode)];
 
         let commands = [
-            Command::new(Instruction::SWRESET, &[], 200),
-            Command::new(Instruction::SLPOUT, &[], 200),
-            Command::new(Instruction::FRMCTR1, &[0x01, 0x2C, 0x2D], 0),
-            Command::new(Instruction::FRMCTR2, &[0x01, 0x2C, 0x2D], 0),
-            Command::new(
-                Instruction::FRMCTR3,
-                &[0x01, 0x2C, 0x2D, 0x01, 0x2C, 0x2D],
-                0,
-            ),
-            Command::new(Instruction::INVCTR, &[0x07], 0),
-            Command::new(Instruction::PWCTR1, &[0xA2, 0x02, 0x84], 0),
-            Command::new(Instruction::PWCTR2, &[0xC5], 0),
-            Command::new(Instruction::PWCTR3, &[0x0A, 0x00], 0),
-            Command::new(Instruction::PWCTR4, &[0x8A, 0x2A], 0),
-            Command::new(Instruction::PWCTR5, &[0x8A, 0xEE], 0),
-            Command::new(Instruction::VMCTR1, &[0x0E], 0),
             Command::new(
-                if inverted {
+                if self.inverted {
                     Instruction::INVON
                 } else {
                     Instruction::INVOFF
-                },
+                } as u8,
                 &[],
                 0,
             ),
-            Command::new(Instruction::MADCTL, if rgb { &[0x00] } else { &[0x08] }, 0),
-            Command::new(Instruction::COLMOD, &[0x05], 0),
-            Command::new(Instruction::DISPON, &[], 200),
+            Command::new(Instruction::COLMOD as u8, &colmod, 0),
+            Command::new(Instruction::DISPON as u8, &[], 200),
         ];
 
+        // Step the transaction is at, to report where a communication error happened.
+        let mut step = 0;
+        let current = &mut step;
         self.spi
             .transaction(move |bus| async move {
                 let bus = unsafe { &mut *bus };
-                for Command {
-                    instruction,
-                    params,
-                    delay_time,
-                } in commands
+                for (
+                    i,
+                    &Command {
+                        instruction,
+                        params,
+                        delay_time,
+                    },
+                ) in init_sequence.iter().chain(commands.iter()).enumerate()
                 {
-                    dc.set_low().ok();
+                    let op = Op::Init(i as u8);
+                    *current = i as u8;
+                    if let Err(e) = dc.set_low() {
+                        return Ok(Err(Error::Dc(op, e)));
+                    }
                     let mut data = [0_u8; 1];
-                    data.copy_from_slice(&[instruction as u8]);
+                    data.copy_from_slice(&[instruction]);
                     bus.write(&data).await?;
                     if !params.is_empty() {
-                        dc.set_high().ok();
-                        let mut buf = [0_u8; 8];
+                        if let Err(e) = dc.set_high() {
+                            return Ok(Err(Error::Dc(op, e)));
+                        }
+                        // Parameters may live in flash, copy them to RAM for DMA.
+                        let mut buf = [0_u8; 16];
                         buf[..params.len()].copy_from_slice(params);
                         bus.write(&buf[..params.len()]).await?;
                     }
-                    if delay_time > 0 {
-                        delay.delay_ms(delay_time).await.ok();
+                    if delay_time > 0 && delay.delay_ms(delay_time).await.is_err() {
+                        return Ok(Err(Error::Delay(op)));
                     }
                 }
-                Ok(())
+                Ok(Ok(()))
             })
             .await
-            .map_err(Error::Comm)?;
+            .map_err(|e| Error::Comm(Op::Init(step), e))??;
 
-        self.set_orientation(self.orientation).await?;
+        self.power = PowerState {
+            sleeping: false,
+            display_on: true,
+            idle: false,
+        };
         Ok(())
     }
 
-    pub async fn hard_reset<D>(&mut self, delay: &mut D) -> Result<(), Error<E>>
+    /// Resets the controller with the reset pin, or with SWRESET if there is none, and waits
+    /// until it accepts commands again.
+    ///
+    /// Pulse and wait times are set with [`ConfigBuilder::reset_timing`].
+    pub async fn reset<D>(&mut self, delay: &mut D) -> Result<(), Error<E, DCE, RSTE>>
     where
         D: DelayUs,
     {
-        self.rst.set_high().map_err(Error::Pin)?;
-        delay.delay_ms(10).await.ok();
-        self.rst.set_low().map_err(Error::Pin)?;
-        delay.delay_ms(10).await.ok();
-        self.rst.set_high().map_err(Error::Pin)
+        let (pulse_us, settle_ms) = self.reset_timing;
+        let delay_failed = |_| Error::Delay(Op::Reset);
+        match &mut self.rst {
+            Some(rst) => {
+                rst.set_high().map_err(Error::Reset)?;
+                delay.delay_us(pulse_us).await.map_err(delay_failed)?;
+                rst.set_low().map_err(Error::Reset)?;
+                delay.delay_us(pulse_us).await.map_err(delay_failed)?;
+                rst.set_high().map_err(Error::Reset)?;
+            }
+            None => self
+                .write_command(Instruction::SWRESET, &[])
+                .await
+                .map_err(|e| e.during(Op::Reset))?,
+        }
+        delay.delay_ms(settle_ms).await.map_err(delay_failed)
     }
+}
 
-    pub async fn set_orientation(&mut self, orientation: Orientation) -> Result<(), Error<E>> {
-        if self.rgb {
-            self.write_command(Instruction::MADCTL, &[orientation as u8])
-                .await?;
-        } else {
-            self.write_command(Instruction::MADCTL, &[orientation as u8 | 0x08])
-                .await?;
-        }
+impl<SPI, DC, M, E, DCE> ST7735IF<SPI, DC, NoResetPin, M, Uninitialized>
+where
+    SPI: SpiDevice<Error = E>,
+    SPI::Bus: SpiBus,
+    DC: OutputPin<Error = DCE>,
+    M: Model,
+{
+    /// Creates a new driver instance for a module whose reset line is not connected to a pin,
+    /// e.g. tied to the MCU reset. [`init`](Self::init) resets it with SWRESET instead.
+    pub fn new_without_reset(spi: SPI, dc: DC, config: Config<M>) -> Self {
+        Self::with_reset_pin(spi, dc, None, config)
+    }
+}
+
+impl<SPI, DC, RST, M, E, DCE, RSTE> ST7735IF<SPI, DC, RST, M>
+where
+    SPI: SpiDevice<Error = E>,
+    SPI::Bus: SpiBus,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    M: Model,
+{
+    /// Sets the display orientation and recomputes the address offsets of the visible area.
+    pub async fn set_orientation(
+        &mut self,
+        orientation: Orientation,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        self.write_command(
+            Instruction::MADCTL,
+            &[self.madctl(orientation, self.color_order)],
+        )
+        .await?;
         self.orientation = orientation;
+        (self.dx, self.dy) =
+            orientation.ram_offset(M::ram_size(self.variant), self.size, self.offset);
         Ok(())
     }
 
-    async fn write_command(
+    /// Sets the pixel format sent to the display.
+    ///
+    /// Fails with [`Error::Unsupported`] unless `color_mode` is one of the model's
+    /// [`COLOR_MODES`](Model::COLOR_MODES).
+    pub async fn set_color_mode(
         &mut self,
-        instruction: Instruction,
-        params: &[u8],
-    ) -> Result<(), Error<E>> {
-        let dc = &mut self.dc;
-        self.spi
-            .transaction(move |bus| async move {
-                let bus = unsafe { &mut *bus };
-                dc.set_low().ok();
-                let mut data = [0_u8; 1];
-                data.copy_from_slice(&[instruction as u8]);
-                bus.write(&data).await?;
-                if !params.is_empty() {
-                    dc.set_high().ok();
-                    let mut buf = [0_u8; 8];
-                    buf[..params.len()].copy_from_slice(params);
-                    bus.write(&buf[..params.len()]).await?;
-                }
-                Ok(())
-            })
-            .await
-            .map_err(Error::Comm)?;
+        color_mode: ColorMode,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        if !M::COLOR_MODES.contains(&color_mode) {
+            return Err(Error::Unsupported);
+        }
+        self.write_command(Instruction::COLMOD, &[M::colmod(color_mode)])
+            .await?;
+        self.color_mode = color_mode;
         Ok(())
     }
 
-    fn start_data(&mut self) -> Result<(), Error<E>> {
-        self.dc.set_high().map_err(Error::Pin)
+    /// Sets the rows moved by [`set_scroll_offset`](Self::set_scroll_offset), leaving `top_fixed`
+    /// and `bottom_fixed` rows in place, and resets the scroll offset.
+    ///
+    /// Rows are those of the panel in its native (portrait) orientation. Scrolling always moves
+    /// along them, which is horizontal on screen in landscape orientations.
+    ///
+    /// Fails with [`Error::InvalidGeometry`] if the fixed rows leave no rows to scroll.
+    pub async fn set_scroll_area(
+        &mut self,
+        top_fixed: u16,
+        bottom_fixed: u16,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        let height = self.size.1;
+        if top_fixed as u32 + bottom_fixed as u32 >= height as u32 {
+            return Err(Error::InvalidGeometry);
+        }
+        let scrolled = height - top_fixed - bottom_fixed;
+        // Frame memory rows outside of the visible area are fixed as well.
+        let top = self.offset.1 + top_fixed;
+        let bottom = M::ram_size(self.variant).1 - top - scrolled;
+        let (top, scrolled, bottom) = (
+            top.to_be_bytes(),
+            scrolled.to_be_bytes(),
+            bottom.to_be_bytes(),
+        );
+        self.write_command(
+            Instruction::VSCRDEF,
+            &[
+                top[0],
+                top[1],
+                scrolled[0],
+                scrolled[1],
+                bottom[0],
+                bottom[1],
+            ],
+        )
+        .await?;
+        self.scroll_area = Some((top_fixed, height - top_fixed - bottom_fixed));
+        self.write_scroll_offset(0).await
     }
 
-    async fn write_data(&mut self, data: &[u8]) -> Result<(), Error<E>> {
-        let mut buf = [0_u8; 8];
-        buf[..data.len()].copy_from_slice(data);
-        self.spi
-            .write(&buf[..data.len()])
+    /// Scrolls the content of the scroll area `offset` rows towards the first row of the native
+    /// orientation, wrapping around, without sending it again.
+    ///
+    /// The scroll area is the whole panel unless set with
+    /// [`set_scroll_area`](Self::set_scroll_area).
+    pub async fn set_scroll_offset(&mut self, offset: u16) -> Result<(), Error<E, DCE, RSTE>> {
+        if self.scroll_area.is_none() {
+            self.set_scroll_area(0, 0).await?;
+        }
+        self.write_scroll_offset(offset).await
+    }
+
+    /// Only shows `rows` of the panel, blanking the others, which takes less power.
+    ///
+    /// Rows are those of the panel in its native (portrait) orientation, like for
+    /// [`set_scroll_area`](Self::set_scroll_area). Drawing works as usual, only the rows in the
+    /// partial area are shown.
+    ///
+    /// Fails with [`Error::InvalidGeometry`] if `rows` is empty or extends past the panel.
+    pub async fn enter_partial_mode(
+        &mut self,
+        rows: RangeInclusive<u16>,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        let (start, end) = (*rows.start(), *rows.end());
+        if start > end || end >= self.size.1 {
+            return Err(Error::InvalidGeometry);
+        }
+        let start = (self.offset.1 + start).to_be_bytes();
+        let end = (self.offset.1 + end).to_be_bytes();
+        self.write_command(Instruction::PTLAR, &[start[0], start[1], end[0], end[1]])
+            .await?;
+        self.write_command(Instruction::PTLON, &[]).await
+    }
+
+    /// Shows the whole panel again after [`enter_partial_mode`](Self::enter_partial_mode).
+    pub async fn exit_partial_mode(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        self.write_command(Instruction::NORON, &[]).await
+    }
+
+    /// Puts the panel to sleep, stopping its oscillator and booster. Frame memory keeps its
+    /// content.
+    ///
+    /// On failure the driver is given back with the error. If only the wait after SLPIN failed,
+    /// [`power_state`](Self::power_state) already reports the panel asleep.
+    pub async fn sleep<D: DelayUs>(
+        mut self,
+        delay: &mut D,
+    ) -> Result<ST7735IF<SPI, DC, RST, M, Sleeping>, StateError<Self, E, DCE, RSTE>> {
+        match self.enter_sleep(delay).await {
+            Ok(()) => Ok(self.into_state()),
+            Err(error) => Err(StateError {
+                error,
+                driver: self,
+            }),
+        }
+    }
+
+    async fn enter_sleep<D: DelayUs>(&mut self, delay: &mut D) -> Result<(), Error<E, DCE, RSTE>> {
+        self.write_command(Instruction::SLPIN, &[]).await?;
+        self.power.sleeping = true;
+        // The panel takes 120 ms before it accepts SLPOUT.
+        delay
+            .delay_ms(120)
             .await
-            .map_err(Error::Comm)
+            .map_err(|_| Error::Delay(Op::Command(Instruction::SLPIN)))
     }
 
-    /// Sets the global offset of the displayed image
-    pub fn set_offset(&mut self, dx: u16, dy: u16) {
-        self.dx = dx;
-        self.dy = dy;
+    /// Blanks the panel, keeping frame memory and the controller running.
+    ///
+    /// Fails with [`Error::InvalidState`] if the display is already off.
+    pub async fn display_off(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        if !self.power.display_on {
+            return Err(Error::InvalidState);
+        }
+        self.write_command(Instruction::DISPOFF, &[]).await?;
+        self.power.display_on = false;
+        Ok(())
+    }
+
+    /// Shows frame memory again after [`display_off`](Self::display_off).
+    ///
+    /// Fails with [`Error::InvalidState`] if the display is already on.
+    pub async fn display_on(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        if self.power.display_on {
+            return Err(Error::InvalidState);
+        }
+        self.write_command(Instruction::DISPON, &[]).await?;
+        self.power.display_on = true;
+        Ok(())
+    }
+
+    /// Reduces colors to 8, the most significant bit of each channel, which takes less power.
+    ///
+    /// Fails with [`Error::InvalidState`] if idle mode is already on.
+    pub async fn enter_idle_mode(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        if self.power.idle {
+            return Err(Error::InvalidState);
+        }
+        self.write_command(Instruction::IDMON, &[]).await?;
+        self.power.idle = true;
+        Ok(())
+    }
+
+    /// Shows all colors again after [`enter_idle_mode`](Self::enter_idle_mode).
+    ///
+    /// Fails with [`Error::InvalidState`] if idle mode is off.
+    pub async fn exit_idle_mode(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        if !self.power.idle {
+            return Err(Error::InvalidState);
+        }
+        self.write_command(Instruction::IDMOFF, &[]).await?;
+        self.power.idle = false;
+        Ok(())
+    }
+
+    /// Scrolls the content of the scroll area `rows` rows towards the start of the screen axis it
+    /// moves along, up or left.
+    pub(crate) async fn scroll_screen(&mut self, rows: u16) -> Result<(), Error<E, DCE, RSTE>> {
+        let (_, height) = self.scroll_area.unwrap_or((0, self.size.1));
+        let (offset, rows) = (self.scroll_offset, rows % height);
+        // Mirrored rows scroll the other way on screen.
+        let offset = if self.orientation.mirror_y() {
+            offset + height - rows
+        } else {
+            offset + rows
+        };
+        self.set_scroll_offset(offset % height).await
+    }
+
+    /// Position in frame memory shown at `pos` along the screen axis the scroll area moves along.
+    pub(crate) fn scrolled(&self, pos: u16) -> u16 {
+        let (top, height) = match self.scroll_area {
+            Some(area) if self.scroll_offset > 0 => area,
+            _ => return pos,
+        };
+        let rows = self.size.1;
+        let mirror = self.orientation.mirror_y();
+        if pos >= rows {
+            return pos;
+        }
+        let row = if mirror { rows - 1 - pos } else { pos };
+        if row < top || row - top >= height {
+            return pos;
+        }
+        let row = top + (row - top + self.scroll_offset) % height;
+        if mirror {
+            rows - 1 - row
+        } else {
+            row
+        }
+    }
+
+    async fn write_scroll_offset(&mut self, offset: u16) -> Result<(), Error<E, DCE, RSTE>> {
+        let (top, height) = self.scroll_area.unwrap_or((0, self.size.1));
+        let offset = offset % height;
+        let start = (self.offset.1 + top + offset).to_be_bytes();
+        self.write_command(Instruction::VSCRSADD, &start).await?;
+        self.scroll_offset = offset;
+        Ok(())
+    }
+
+    /// Switches to the color mode and channel order of `C` if the display is set to others.
+    async fn select_color<C: DisplayColor>(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        if self.color_mode != C::COLOR_MODE {
+            self.set_color_mode(C::COLOR_MODE).await?;
+        }
+        if self.color_order != C::COLOR_ORDER {
+            let madctl = self.madctl(self.orientation, C::COLOR_ORDER);
+            self.write_command(Instruction::MADCTL, &[madctl]).await?;
+            self.color_order = C::COLOR_ORDER;
+        }
+        Ok(())
+    }
+
+    /// MADCTL parameter for `orientation` with pixel data in `order`.
+    ///
+    /// Data in the panel's own subpixel order goes through unchanged, the color order bit is only
+    /// set when they differ.
+    fn madctl(&self, orientation: Orientation, order: ColorOrder) -> u8 {
+        M::madctl(orientation, self.rgb == (order == ColorOrder::Rgb))
+    }
+
+    /// Sets the offset of the visible area in frame memory, in the native (portrait) orientation.
+    ///
+    /// The offset used for the current orientation is derived from it, see
+    /// [`set_orientation`](Self::set_orientation).
+    ///
+    /// Fails with [`Error::InvalidGeometry`] if the visible area moved by the offset does not fit
+    /// in frame memory.
+    pub fn set_offset(&mut self, dx: u16, dy: u16) -> Result<(), Error<E, DCE, RSTE>> {
+        let ram = M::ram_size(self.variant);
+        if self.size.0 as u32 + dx as u32 > ram.0 as u32
+            || self.size.1 as u32 + dy as u32 > ram.1 as u32
+        {
+            return Err(Error::InvalidGeometry);
+        }
+        self.offset = (dx, dy);
+        (self.dx, self.dy) = self.orientation.ram_offset(ram, self.size, self.offset);
+        Ok(())
     }
 
     /// Sets the address window for the display.
+    ///
+    /// Fails with [`Error::InvalidGeometry`] if the window is empty or extends past the display.
     pub async fn set_address_window(
         &mut self,
         sx: u16,
         sy: u16,
         ex: u16,
         ey: u16,
-    ) -> Result<(), Error<E>> {
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        let size = self.size();
+        if sx > ex || sy > ey || ex as u32 >= size.width || ey as u32 >= size.height {
+            return Err(Error::InvalidGeometry);
+        }
+        self.write_address_window(sx, sy, ex, ey)
+            .await
+            .map_err(|e| e.during(Op::AddressWindow))
+    }
+
+    async fn write_address_window(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        ex: u16,
+        ey: u16,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
         self.write_command(Instruction::CASET, &[]).await?;
         self.start_data()?;
         let sx_bytes = (sx + self.dx).to_be_bytes();
@@ -271,109 +719,784 @@ where
             .await
     }
 
-    pub async fn flush_frame<const N: usize>(&mut self, frame: &Frame<N>) -> Result<(), Error<E>> {
-        self.set_address_window(0, 0, frame.width as u16 - 1, frame.height as u16 - 1)
+    /// Sends the whole `frame`, switching to its color mode first if needed.
+    ///
+    /// Fails with [`Error::InvalidGeometry`] if `frame` is empty or its buffer is smaller than its
+    /// size.
+    pub async fn flush_frame<const N: usize, C: DisplayColor>(
+        &mut self,
+        frame: &Frame<N, C>,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        frame.check_buffer().map_err(|_| Error::InvalidGeometry)?;
+        let (ex, ey) = frame_end(frame.width, frame.height, 1).ok_or(Error::InvalidGeometry)?;
+        self.select_color::<C>().await?;
+        self.set_address_window(0, 0, ex, ey).await?;
+        self.write_command(Instruction::RAMWR, &[]).await?;
+        self.start_data()?;
+        self.spi
+            .write(&frame.buffer)
+            .await
+            .map_err(comm(Op::MemoryWrite))
+    }
+
+    /// Sends the whole `frame` enlarged `scale` times, repeating every pixel and row.
+    ///
+    /// Rows are expanded into a small line buffer as they are sent, so a frame of a quarter or a
+    /// sixteenth of the display size fills all of it.
+    ///
+    /// Fails with [`Error::InvalidGeometry`] if `frame` is empty, its buffer is smaller than its
+    /// size or the enlarged frame doesn't fit on the display.
+    pub async fn flush_frame_scaled<const N: usize, C: DisplayColor>(
+        &mut self,
+        frame: &Frame<N, C>,
+        scale: Scale,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        let scale = scale as usize;
+        frame.check_buffer().map_err(|_| Error::InvalidGeometry)?;
+        let (ex, ey) = frame_end(frame.width, frame.height, scale).ok_or(Error::InvalidGeometry)?;
+        let (width, height) = (frame.width as usize, frame.height as usize);
+        self.select_color::<C>().await?;
+        self.set_address_window(0, 0, ex, ey).await?;
+        self.write_command(Instruction::RAMWR, &[]).await?;
+        self.start_data()?;
+        self.spi
+            .transaction(move |bus| async move {
+                let bus = unsafe { &mut *bus };
+                let capacity = direct::line_capacity::<C>();
+                let mut buf = [0_u8; direct::LINE_BUFFER];
+                for y in 0..height * scale {
+                    let row = y / scale * width;
+                    let mut len = 0;
+                    for x in 0..width {
+                        let color = C::load(&frame.buffer, row + x);
+                        for _ in 0..scale {
+                            if len == capacity {
+                                bus.write(&buf[..C::bytes(len)]).await?;
+                                len = 0;
+                            }
+                            color.store(&mut buf, len);
+                            len += 1;
+                        }
+                    }
+                    bus.write(&buf[..C::bytes(len)]).await?;
+                }
+                Ok(())
+            })
+            .await
+            .map_err(comm(Op::MemoryWrite))
+    }
+
+    /// Sends only the tiles of `frame` that changed since the last frame sent with `diff`.
+    ///
+    /// Falls back to [`flush_frame`](Self::flush_frame) when the display content is unknown or
+    /// most tiles changed, see [`FrameDiff`].
+    ///
+    /// Fails with [`Error::InvalidGeometry`] if `frame` has more tiles than `diff` tracks or its
+    /// buffer is smaller than its size.
+    pub async fn flush_frame_diff<const N: usize, const T: usize, C: DisplayColor>(
+        &mut self,
+        frame: &Frame<N, C>,
+        diff: &mut FrameDiff<T>,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        let mut dirty = DirtyRegions::new();
+        let full = diff
+            .update(frame, &mut dirty)
+            .ok_or(Error::InvalidGeometry)?;
+        // The display may have been switched to another color type since the last full flush.
+        let result = match self.select_color::<C>().await {
+            Err(e) => Err(e),
+            Ok(()) if full => self.flush_frame(frame).await,
+            Ok(()) => {
+                self.write_rects::<C>(&frame.buffer, frame.width as u16, &dirty)
+                    .await
+            }
+        };
+        if result.is_err() {
+            diff.invalidate();
+        }
+        result
+    }
+
+    /// Writes the `rects` areas of a row-major framebuffer of `C` pixels, `width` pixels wide.
+    async fn write_rects<C: DisplayColor>(
+        &mut self,
+        buffer: &[u8],
+        width: u16,
+        rects: &DirtyRegions,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        for &rect in rects.iter() {
+            self.write_rect::<C>(buffer, width, rect).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes the `rect` area of a row-major framebuffer of `C` pixels, `width` pixels wide, to
+    /// the same area of the display, streaming it row by row in one transaction.
+    ///
+    /// The area is widened to whole bytes for colors packing several pixels per byte.
+    async fn write_rect<C: DisplayColor>(
+        &mut self,
+        buffer: &[u8],
+        width: u16,
+        rect: Rect,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        let align = C::ALIGN as u16;
+        let rect = if width.is_multiple_of(align) {
+            Rect::new(
+                rect.x0 - rect.x0 % align,
+                rect.y0,
+                (rect.x1 / align + 1) * align - 1,
+                rect.y1,
+            )
+        } else {
+            // Rows don't start on a byte boundary, send everything from the first pixel.
+            Rect::new(0, 0, width - 1, rect.y1)
+        };
+        self.set_address_window(rect.x0, rect.y0, rect.x1, rect.y1)
             .await?;
         self.write_command(Instruction::RAMWR, &[]).await?;
         self.start_data()?;
-        self.spi.write(&frame.buffer).await.map_err(Error::Comm)
+        let width = width as usize;
+        self.spi
+            .transaction(move |bus| async move {
+                let bus = unsafe { &mut *bus };
+                let (x0, x1) = (rect.x0 as usize, rect.x1 as usize);
+                if x1 - x0 + 1 == width {
+                    // Full rows are contiguous in the buffer.
+                    let start = rect.y0 as usize * width * C::BITS / 8;
+                    let end = C::bytes((rect.y1 as usize + 1) * width);
+                    bus.write(&buffer[start..end]).await?;
+                } else {
+                    for y in rect.y0 as usize..=rect.y1 as usize {
+                        let row = y * width;
+                        let start = (row + x0) * C::BITS / 8;
+                        let end = C::bytes(row + x1 + 1);
+                        bus.write(&buffer[start..end]).await?;
+                    }
+                }
+                Ok(())
+            })
+            .await
+            .map_err(comm(Op::MemoryWrite))
     }
 }
 
-impl<SPI, DC, RST, E> ST7735<SPI, DC, RST>
+impl<SPI, DC, RST, M, E, DCE, RSTE> ST7735IF<SPI, DC, RST, M, Sleeping>
 where
     SPI: SpiDevice<Error = E>,
     SPI::Bus: SpiBus,
-    DC: OutputPin<Error = Infallible>,
-    RST: OutputPin<Error = Infallible>,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    M: Model,
 {
-    /// Creates a new driver instance that uses hardware SPI.
-    pub fn new(spi: SPI, dc: DC, rst: RST, config: Config, width: u32, height: u32) -> Self {
+    /// Wakes the panel up after [`sleep`](ST7735IF::sleep).
+    ///
+    /// On failure the sleeping driver is given back with the error. If only the wait after
+    /// SLPOUT failed, [`power_state`](ST7735IF::power_state) already reports the panel awake.
+    pub async fn wake<D: DelayUs>(
+        mut self,
+        delay: &mut D,
+    ) -> Result<ST7735IF<SPI, DC, RST, M>, StateError<Self, E, DCE, RSTE>> {
+        match self.leave_sleep(delay).await {
+            Ok(()) => Ok(self.into_state()),
+            Err(error) => Err(StateError {
+                error,
+                driver: self,
+            }),
+        }
+    }
+
+    async fn leave_sleep<D: DelayUs>(&mut self, delay: &mut D) -> Result<(), Error<E, DCE, RSTE>> {
+        self.write_command(Instruction::SLPOUT, &[]).await?;
+        self.power.sleeping = false;
+        // The supply voltages settle within 120 ms, SLPIN is not accepted before.
+        delay
+            .delay_ms(120)
+            .await
+            .map_err(|_| Error::Delay(Op::Command(Instruction::SLPOUT)))
+    }
+}
+
+impl<SPI, DC, RST, M, S, E, DCE, RSTE> ST7735IF<SPI, DC, RST, M, S>
+where
+    SPI: SpiDevice<Error = E>,
+    SPI::Bus: SpiBus,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    M: Model,
+{
+    /// Current power state.
+    pub fn power_state(&self) -> PowerState {
+        self.power
+    }
+
+    /// Gives back the SPI device and the pins, the reset pin if there is one.
+    pub fn release(self) -> (SPI, DC, Option<RST>) {
+        (self.spi, self.dc, self.rst)
+    }
+
+    /// Moves the driver to lifecycle state `T`.
+    fn into_state<T>(self) -> ST7735IF<SPI, DC, RST, M, T> {
+        ST7735IF {
+            spi: self.spi,
+            dc: self.dc,
+            rst: self.rst,
+            reset_timing: self.reset_timing,
+            rgb: self.rgb,
+            inverted: self.inverted,
+            color_mode: self.color_mode,
+            color_order: self.color_order,
+            size: self.size,
+            offset: self.offset,
+            dx: self.dx,
+            dy: self.dy,
+            orientation: self.orientation,
+            scroll_area: self.scroll_area,
+            scroll_offset: self.scroll_offset,
+            power: self.power,
+            variant: self.variant,
+            state: PhantomData,
+        }
+    }
+
+    async fn write_command(
+        &mut self,
+        instruction: Instruction,
+        params: &[u8],
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        let op = Op::Command(instruction);
+        let dc = &mut self.dc;
+        self.spi
+            .transaction(move |bus| async move {
+                let bus = unsafe { &mut *bus };
+                if let Err(e) = dc.set_low() {
+                    return Ok(Err(Error::Dc(op, e)));
+                }
+                let mut data = [0_u8; 1];
+                data.copy_from_slice(&[instruction as u8]);
+                bus.write(&data).await?;
+                if !params.is_empty() {
+                    if let Err(e) = dc.set_high() {
+                        return Ok(Err(Error::Dc(op, e)));
+                    }
+                    let mut buf = [0_u8; 8];
+                    buf[..params.len()].copy_from_slice(params);
+                    bus.write(&buf[..params.len()]).await?;
+                }
+                Ok(Ok(()))
+            })
+            .await
+            .map_err(comm(op))?
+    }
+
+    /// Sets DC for the pixel data following RAMWR.
+    fn start_data(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        self.dc.set_high().map_err(dc(Op::MemoryWrite))
+    }
+
+    async fn write_data(&mut self, data: &[u8]) -> Result<(), Error<E, DCE, RSTE>> {
+        let mut buf = [0_u8; 8];
+        buf[..data.len()].copy_from_slice(data);
+        self.spi
+            .write(&buf[..data.len()])
+            .await
+            .map_err(comm(Op::AddressWindow))
+    }
+}
+
+impl<SPI, DC, RST, BUF, M, C, E, DCE, RSTE, S, const W: usize, const H: usize>
+    ST7735<SPI, DC, RST, W, H, BUF, M, C, S>
+where
+    SPI: SpiDevice<Error = E>,
+    SPI::Bus: SpiBus,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    BUF: AsMut<[u8]>,
+    M: Model,
+    C: DisplayColor,
+{
+    /// Fails the build if the framebuffer can't fit in the controller's frame memory.
+    const FITS_RAM: () = assert!(
+        (W <= M::RAM_WIDTH as usize && H <= M::RAM_HEIGHT as usize)
+            || (W <= M::RAM_HEIGHT as usize && H <= M::RAM_WIDTH as usize),
+        "framebuffer is larger than the controller's frame memory"
+    );
+
+    /// Size of the framebuffer in bytes.
+    const BUF_LEN: usize = W * H * C::BITS / 8;
+
+    /// Current power state.
+    pub fn power_state(&self) -> PowerState {
+        self.iface.power_state()
+    }
+
+    /// Gives back the SPI device, the pins and the framebuffer storage.
+    pub fn release(self) -> (SPI, DC, Option<RST>, BUF) {
+        let (spi, dc, rst) = self.iface.release();
+        (spi, dc, rst, self.buffer)
+    }
+}
+
+impl<SPI, DC, RST, BUF, M, C, E, DCE, RSTE, const W: usize, const H: usize>
+    ST7735<SPI, DC, RST, W, H, BUF, M, C, Uninitialized>
+where
+    SPI: SpiDevice<Error = E>,
+    SPI::Bus: SpiBus,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    BUF: AsMut<[u8]>,
+    M: Model,
+    C: DisplayColor,
+{
+    /// Creates a new driver instance that uses hardware SPI, drawing into `buffer`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `W` x `H` is not the panel size of `config` in its initial orientation, if the
+    /// color mode of `config` is not the one of `C`, or if `buffer` is too short for `W * H`
+    /// pixels of `C`.
+    pub fn new(spi: SPI, dc: DC, rst: RST, config: Config<M>, buffer: BUF) -> Self {
+        Self::with_iface(ST7735IF::new(spi, dc, rst, config), buffer)
+    }
+
+    fn with_iface(mut iface: ST7735IF<SPI, DC, RST, M, Uninitialized>, mut buffer: BUF) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::FITS_RAM;
+        let (width, height) = if iface.orientation.swap_axes() {
+            (iface.size.1, iface.size.0)
+        } else {
+            iface.size
+        };
+        assert!(
+            (width as usize, height as usize) == (W, H),
+            "framebuffer size does not match the configured panel size"
+        );
+        assert!(
+            iface.color_mode == C::COLOR_MODE,
+            "framebuffer color type does not match the configured color mode"
+        );
+        assert!(
+            buffer.as_mut().len() >= Self::BUF_LEN,
+            "framebuffer storage is too small"
+        );
+        let mut dirty = DirtyRegions::new();
+        dirty.add(Rect::new(0, 0, width - 1, height - 1));
+        // Sent with the orientation during `init`.
+        iface.color_order = C::COLOR_ORDER;
         Self {
-            iface: ST7735IF::new(spi, dc, rst, config),
-            width,
-            height,
-            buffer: [0; BUF_SIZE],
+            iface,
+            width: width as u32,
+            height: height as u32,
+            buffer,
+            dirty,
+            color: PhantomData,
         }
     }
 
-    /// Runs commands to initialize the display.
-    pub async fn init<D>(&mut self, delay: &mut D) -> Result<(), Error<E>>
+    /// Runs commands to initialize the display, returning a driver ready to draw.
+    ///
+    /// On failure the uninitialized driver is given back with the error.
+    pub async fn init<D>(
+        self,
+        delay: &mut D,
+    ) -> Result<ST7735<SPI, DC, RST, W, H, BUF, M, C>, StateError<Self, E, DCE, RSTE>>
     where
         D: DelayUs,
     {
-        self.iface.init(delay).await?;
+        let ST7735 {
+            iface,
+            width,
+            height,
+            buffer,
+            dirty,
+            color,
+        } = self;
+        match iface.init(delay).await {
+            Ok(iface) => Ok(ST7735 {
+                iface,
+                width,
+                height,
+                buffer,
+                dirty,
+                color,
+            }),
+            Err(e) => Err(e.map_driver(|iface| ST7735 {
+                iface,
+                width,
+                height,
+                buffer,
+                dirty,
+                color,
+            })),
+        }
+    }
+}
 
-        Ok(())
+impl<SPI, DC, BUF, M, C, E, DCE, const W: usize, const H: usize>
+    ST7735<SPI, DC, NoResetPin, W, H, BUF, M, C, Uninitialized>
+where
+    SPI: SpiDevice<Error = E>,
+    SPI::Bus: SpiBus,
+    DC: OutputPin<Error = DCE>,
+    BUF: AsMut<[u8]>,
+    M: Model,
+    C: DisplayColor,
+{
+    /// Creates a new driver instance without a reset pin, see [`ST7735IF::new_without_reset`].
+    ///
+    /// # Panics
+    ///
+    /// Panics like [`new`](ST7735::new).
+    pub fn new_without_reset(spi: SPI, dc: DC, config: Config<M>, buffer: BUF) -> Self {
+        Self::with_iface(ST7735IF::new_without_reset(spi, dc, config), buffer)
     }
+}
 
-    pub async fn flush(&mut self) -> Result<(), Error<E>> {
+impl<SPI, DC, RST, BUF, M, C, E, DCE, RSTE, const W: usize, const H: usize>
+    ST7735<SPI, DC, RST, W, H, BUF, M, C>
+where
+    SPI: SpiDevice<Error = E>,
+    SPI::Bus: SpiBus,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    BUF: AsMut<[u8]>,
+    M: Model,
+    C: DisplayColor,
+{
+    pub async fn flush(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
         self.iface
             .set_address_window(0, 0, self.width as u16 - 1, self.height as u16 - 1)
             .await?;
         self.iface.write_command(Instruction::RAMWR, &[]).await?;
         self.iface.start_data()?;
-        let buf = &self.buffer;
-        self.iface.spi.write(buf).await.map_err(Error::Comm)
+        let buf = &self.buffer.as_mut()[..Self::BUF_LEN];
+        self.iface
+            .spi
+            .write(buf)
+            .await
+            .map_err(comm(Op::MemoryWrite))?;
+        self.dirty.clear();
+        Ok(())
     }
 
-    pub async fn flush_buffer(&mut self, buf: &[u8]) -> Result<(), Error<E>> {
+    /// Sends only the areas of the framebuffer changed since the last flush.
+    ///
+    /// Everything is dirty after creation, [`flush_buffer`](Self::flush_buffer) and
+    /// [`set_orientation`](Self::set_orientation).
+    pub async fn flush_dirty(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        let buf = &self.buffer.as_mut()[..Self::BUF_LEN];
+        self.iface
+            .write_rects::<C>(buf, self.width as u16, &self.dirty)
+            .await?;
+        self.dirty.clear();
+        Ok(())
+    }
+
+    pub async fn flush_buffer(&mut self, buf: &[u8]) -> Result<(), Error<E, DCE, RSTE>> {
         self.iface
             .set_address_window(0, 0, self.width as u16 - 1, self.height as u16 - 1)
             .await?;
         self.iface.write_command(Instruction::RAMWR, &[]).await?;
         self.iface.start_data()?;
-        self.iface.spi.write(buf).await.map_err(Error::Comm)
+        // The display no longer shows the framebuffer.
+        self.mark_all_dirty();
+        self.iface
+            .spi
+            .write(buf)
+            .await
+            .map_err(comm(Op::MemoryWrite))
+    }
+
+    /// Sets the display orientation.
+    ///
+    /// The logical size follows the orientation; the framebuffer content is not rotated and
+    /// should be redrawn before the next flush.
+    pub async fn set_orientation(
+        &mut self,
+        orientation: Orientation,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        let old = self.iface.orientation;
+        self.iface.set_orientation(orientation).await?;
+        (self.width, self.height) = old.reorient(orientation, (self.width, self.height));
+        self.mark_all_dirty();
+        Ok(())
+    }
+
+    /// Sets the area scrolled by [`scroll`](Self::scroll), see [`ST7735IF::set_scroll_area`].
+    pub async fn set_scroll_area(
+        &mut self,
+        top_fixed: u16,
+        bottom_fixed: u16,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        self.iface.set_scroll_area(top_fixed, bottom_fixed).await
+    }
+
+    /// Scrolls the content of the scroll area `rows` rows up, or left in landscape orientations,
+    /// wrapping around.
+    ///
+    /// Only the new scroll offset is sent. Drawing keeps using screen coordinates and lands in the
+    /// framebuffer where the scrolled content shows it, so the next
+    /// [`flush_dirty`](Self::flush_dirty) only sends what was drawn since.
+    pub async fn scroll(&mut self, rows: u16) -> Result<(), Error<E, DCE, RSTE>> {
+        self.iface.scroll_screen(rows).await
+    }
+
+    /// Only shows `rows` of the panel, see [`ST7735IF::enter_partial_mode`].
+    pub async fn enter_partial_mode(
+        &mut self,
+        rows: RangeInclusive<u16>,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        self.iface.enter_partial_mode(rows).await
+    }
+
+    /// Shows the whole panel again, see [`ST7735IF::exit_partial_mode`].
+    pub async fn exit_partial_mode(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        self.iface.exit_partial_mode().await
+    }
+
+    /// Puts the panel to sleep, see [`ST7735IF::sleep`].
+    pub async fn sleep<D: DelayUs>(
+        self,
+        delay: &mut D,
+    ) -> Result<ST7735<SPI, DC, RST, W, H, BUF, M, C, Sleeping>, StateError<Self, E, DCE, RSTE>>
+    {
+        let ST7735 {
+            iface,
+            width,
+            height,
+            buffer,
+            dirty,
+            color,
+        } = self;
+        match iface.sleep(delay).await {
+            Ok(iface) => Ok(ST7735 {
+                iface,
+                width,
+                height,
+                buffer,
+                dirty,
+                color,
+            }),
+            Err(e) => Err(e.map_driver(|iface| ST7735 {
+                iface,
+                width,
+                height,
+                buffer,
+                dirty,
+                color,
+            })),
+        }
+    }
+
+    /// Blanks the panel, see [`ST7735IF::display_off`].
+    pub async fn display_off(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        self.iface.display_off().await
+    }
+
+    /// Shows frame memory again, see [`ST7735IF::display_on`].
+    pub async fn display_on(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        self.iface.display_on().await
+    }
+
+    /// Reduces colors to 8, see [`ST7735IF::enter_idle_mode`].
+    pub async fn enter_idle_mode(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        self.iface.enter_idle_mode().await
+    }
+
+    /// Shows all colors again, see [`ST7735IF::exit_idle_mode`].
+    pub async fn exit_idle_mode(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        self.iface.exit_idle_mode().await
+    }
+
+    /// Framebuffer position shown at screen position `(x, y)` with the current scroll offset.
+    fn scrolled(&self, x: u16, y: u16) -> (u16, u16) {
+        if self.iface.orientation.swap_axes() {
+            (self.iface.scrolled(x), y)
+        } else {
+            (x, self.iface.scrolled(y))
+        }
     }
 
     /// Sets a pixel color at the given coords.
-    pub fn set_pixel(&mut self, x: u16, y: u16, color: u16) {
-        let idx = match self.iface.orientation {
-            Orientation::Landscape | Orientation::LandscapeSwapped => {
-                if x as u32 >= self.width {
-                    return;
-                }
-                ((y as usize) * self.width as usize) + (x as usize)
-            }
+    pub fn set_pixel(&mut self, x: u16, y: u16, color: C) {
+        let (x, y) = self.scrolled(x, y);
+        if let Some(idx) = pixel_index(self.width, self.height, x, y) {
+            color.store(&mut self.buffer.as_mut()[..Self::BUF_LEN], idx);
+            self.dirty.add(Rect::point(x, y));
+        }
+    }
+
+    fn mark_all_dirty(&mut self) {
+        self.dirty.clear();
+        self.dirty.add(Rect::new(
+            0,
+            0,
+            self.width as u16 - 1,
+            self.height as u16 - 1,
+        ));
+    }
+}
+
+impl<SPI, DC, RST, BUF, M, C, E, DCE, RSTE, const W: usize, const H: usize>
+    ST7735<SPI, DC, RST, W, H, BUF, M, C, Sleeping>
+where
+    SPI: SpiDevice<Error = E>,
+    SPI::Bus: SpiBus,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    BUF: AsMut<[u8]>,
+    M: Model,
+    C: DisplayColor,
+{
+    /// Wakes the panel up, see [`ST7735IF::wake`].
+    pub async fn wake<D: DelayUs>(
+        self,
+        delay: &mut D,
+    ) -> Result<ST7735<SPI, DC, RST, W, H, BUF, M, C>, StateError<Self, E, DCE, RSTE>> {
+        let ST7735 {
+            iface,
+            width,
+            height,
+            buffer,
+            dirty,
+            color,
+        } = self;
+        match iface.wake(delay).await {
+            Ok(iface) => Ok(ST7735 {
+                iface,
+                width,
+                height,
+                buffer,
+                dirty,
+                color,
+            }),
+            Err(e) => Err(e.map_driver(|iface| ST7735 {
+                iface,
+                width,
+                height,
+                buffer,
+                dirty,
+                color,
+            })),
+        }
+    }
+}
 
-            Orientation::Portrait | Orientation::PortraitSwapped => {
-                if y as u32 >= self.width as u32 {
-                    return;
+/// Index of the pixel at `(x, y)` in a row-major framebuffer of `width` x `height` pixels, or
+/// `None` if the pixel is outside of it.
+///
+/// Framebuffers are laid out in the logical orientation, the controller maps them to the
+/// panel through MADCTL, so the same mapping is valid for every [`Orientation`].
+fn pixel_index(width: u32, height: u32, x: u16, y: u16) -> Option<usize> {
+    if x as u32 >= width || y as u32 >= height {
+        return None;
+    }
+    Some(y as usize * width as usize + x as usize)
+}
+
+/// Last column and row of a frame of `width` x `height` pixels sent enlarged `scale` times, or
+/// `None` if it is empty or larger than the controller can address.
+fn frame_end(width: u32, height: u32, scale: usize) -> Option<(u16, u16)> {
+    let end = |size: u32| {
+        let size = (size as usize).checked_mul(scale)?.checked_sub(1)?;
+        u16::try_from(size).ok()
+    };
+    Some((end(width)?, end(height)?))
+}
+
+/// Pixel indices of row `y` of `rect` in a row-major framebuffer of `C` pixels, `width` pixels
+/// wide, or `None` if the buffer is too short.
+fn rect_row<C: DisplayColor>(
+    buffer: &[u8],
+    width: u32,
+    rect: Rect,
+    y: u16,
+) -> Option<core::ops::Range<usize>> {
+    let start = y as usize * width as usize + rect.x0 as usize;
+    let end = start + rect.width() as usize;
+    if C::bytes(end) <= buffer.len() {
+        Some(start..end)
+    } else {
+        None
+    }
+}
+
+/// Fills `rect` of a row-major framebuffer of `C` pixels, `width` pixels wide, with `color`.
+///
+/// The first row is filled with the bytes of the color and copied into the following rows.
+/// Pixels sharing a byte with their neighbours, at the ends of RGB444 rows, are stored one by
+/// one.
+fn fill_rect<C: DisplayColor>(buffer: &mut [u8], width: u32, rect: Rect, color: C) {
+    // Bytes of `C::ALIGN` pixels, the smallest group that starts and ends on a byte boundary.
+    let mut group = [0_u8; 3];
+    for i in 0..C::ALIGN {
+        color.store(&mut group, i);
+    }
+    let group = &group[..C::bytes(C::ALIGN)];
+    // First pixel of the first row and bytes of its whole-byte part, copied into the rows that
+    // share its alignment.
+    let mut first: Option<(usize, core::ops::Range<usize>)> = None;
+    for y in rect.y0..=rect.y1 {
+        let row = match rect_row::<C>(buffer, width, rect, y) {
+            Some(row) => row,
+            None => break,
+        };
+        let start = (row.start.div_ceil(C::ALIGN) * C::ALIGN).min(row.end);
+        let end = (row.end / C::ALIGN * C::ALIGN).max(start);
+        for idx in (row.start..start).chain(end..row.end) {
+            color.store(buffer, idx);
+        }
+        let bytes = C::bytes(start)..C::bytes(end);
+        match &first {
+            Some((pixel, src)) if (row.start - pixel) % C::ALIGN == 0 => {
+                buffer.copy_within(src.clone(), bytes.start);
+            }
+            _ => {
+                for chunk in buffer[bytes.clone()].chunks_exact_mut(group.len()) {
+                    chunk.copy_from_slice(group);
                 }
-                ((y as usize) * self.height as usize) + (x as usize)
+                first = Some((row.start, bytes));
             }
-        } * 2;
+        }
+    }
+}
 
-        // Split 16 bit value into two bytes
-        let low = (color & 0xff) as u8;
-        let high = ((color & 0xff00) >> 8) as u8;
-        if idx >= self.buffer.len() - 1 {
-            return;
+/// Fills `rect` of a row-major framebuffer of `C` pixels, `width` pixels wide, with `colors`,
+/// row by row.
+fn copy_rect<C, I>(buffer: &mut [u8], width: u32, rect: Rect, colors: I)
+where
+    C: DisplayColor,
+    I: IntoIterator<Item = C>,
+{
+    let mut colors = colors.into_iter();
+    for y in rect.y0..=rect.y1 {
+        if let Some(row) = rect_row::<C>(buffer, width, rect, y) {
+            for (idx, color) in row.zip(&mut colors) {
+                color.store(buffer, idx);
+            }
         }
-        self.buffer[idx] = high;
-        self.buffer[idx + 1] = low;
     }
 }
 
 extern crate embedded_graphics_core;
 use self::embedded_graphics_core::{
-    draw_target::DrawTarget,
-    pixelcolor::{
-        raw::{RawData, RawU16},
-        Rgb565,
-    },
-    prelude::*,
+    draw_target::DrawTarget, pixelcolor::Rgb565, prelude::*, primitives::Rectangle,
 };
 
-impl<SPI, DC, RST, E> DrawTarget for ST7735<SPI, DC, RST>
+impl<SPI, DC, RST, BUF, M, C, E, DCE, RSTE, const W: usize, const H: usize> DrawTarget
+    for ST7735<SPI, DC, RST, W, H, BUF, M, C>
 where
     SPI: SpiDevice<Error = E>,
     SPI::Bus: SpiBus,
-    DC: OutputPin<Error = Infallible>,
-    RST: OutputPin<Error = Infallible>,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    BUF: AsMut<[u8]>,
+    M: Model,
+    C: DisplayColor,
 {
-    type Error = ();
-    type Color = Rgb565;
+    /// The framebuffer always fits the display, drawing into it can't fail.
+    type Error = Infallible;
+    type Color = C;
 
     fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
     where
@@ -384,110 +1507,183 @@ where
         pixels
             .into_iter()
             .filter(|Pixel(pos, _color)| bb.contains(*pos))
-            .for_each(|Pixel(pos, color)| {
-                self.set_pixel(pos.x as u16, pos.y as u16, RawU16::from(color).into_inner())
-            });
+            .for_each(|Pixel(pos, color)| self.set_pixel(pos.x as u16, pos.y as u16, color));
 
         Ok(())
     }
 
-    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
-        let c = RawU16::from(color).into_inner();
-        for i in 0..BUF_SIZE {
-            assert!(i < self.buffer.len());
-            self.buffer[i] = if i % 2 == 0 {
-                ((c & 0xff00) >> 8) as u8
-            } else {
-                (c & 0xff) as u8
-            };
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        // Scrolled areas may wrap around in the framebuffer.
+        if area.intersection(&self.bounding_box()) != *area || self.iface.scroll_offset > 0 {
+            return self.draw_iter(area.points().zip(colors).map(|(p, c)| Pixel(p, c)));
+        }
+        if let Some(rect) = Rect::from_area(area) {
+            copy_rect(
+                &mut self.buffer.as_mut()[..Self::BUF_LEN],
+                self.width,
+                rect,
+                colors,
+            );
+            self.dirty.add(rect);
+        }
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.bounding_box());
+        if self.iface.scroll_offset > 0 && area != self.bounding_box() {
+            return self.draw_iter(area.points().map(|p| Pixel(p, color)));
+        }
+        if let Some(rect) = Rect::from_area(&area) {
+            fill_rect(
+                &mut self.buffer.as_mut()[..Self::BUF_LEN],
+                self.width,
+                rect,
+                color,
+            );
+            self.dirty.add(rect);
         }
         Ok(())
     }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.fill_solid(&self.bounding_box(), color)
+    }
 }
 
-impl<SPI, DC, RST, E> OriginDimensions for ST7735<SPI, DC, RST>
+impl<SPI, DC, RST, BUF, M, C, E, DCE, RSTE, S, const W: usize, const H: usize> OriginDimensions
+    for ST7735<SPI, DC, RST, W, H, BUF, M, C, S>
 where
     SPI: SpiDevice<Error = E>,
     SPI::Bus: SpiBus,
-    DC: OutputPin<Error = Infallible>,
-    RST: OutputPin<Error = Infallible>,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    BUF: AsMut<[u8]>,
+    M: Model,
+    C: DisplayColor,
 {
     fn size(&self) -> Size {
         Size::new(self.width, self.height)
     }
 }
 
-#[derive(Debug)]
-pub enum Error<E = ()> {
-    /// Communication error
-    Comm(E),
-    /// Pin setting error
-    Pin(Infallible),
+/// Enlargement of a frame sent with [`ST7735IF::flush_frame_scaled`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scale {
+    /// Every pixel becomes 2x2 pixels.
+    X2 = 2,
+    /// Every pixel becomes 4x4 pixels.
+    X4 = 4,
 }
 
-pub struct Frame<const N: usize> {
+/// Stand-in for the reset pin of modules whose reset line is not connected to a pin, see
+/// [`ST7735IF::new_without_reset`].
+///
+/// It is never constructed.
+pub enum NoResetPin {}
+
+impl OutputPin for NoResetPin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        match *self {}
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        match *self {}
+    }
+}
+
+/// Power state of the panel, see [`ST7735IF::power_state`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerState {
+    /// Whether the panel is in sleep mode.
+    pub sleeping: bool,
+    /// Whether frame memory is shown.
+    pub display_on: bool,
+    /// Whether idle mode reduces colors to 8.
+    pub idle: bool,
+}
+
+impl PowerState {
+    /// State after a reset, before `init`.
+    const RESET: Self = Self {
+        sleeping: true,
+        display_on: false,
+        idle: false,
+    };
+}
+
+/// Frame of `width` x `height` pixels of `C`, packed in `N` bytes.
+pub struct Frame<const N: usize, C = Rgb565> {
     pub width: u32,
     pub height: u32,
     pub orientation: Orientation,
     pub buffer: [u8; N],
+    color: PhantomData<C>,
 }
 
-impl<const N: usize> Frame<N> {
+impl<const N: usize, C: DisplayColor> Frame<N, C> {
     pub fn new(width: u32, height: u32, orientation: Orientation, buffer: [u8; N]) -> Self {
         Self {
             width,
             height,
             orientation,
             buffer,
+            color: PhantomData,
         }
     }
-    pub fn set_pixel(&mut self, x: u16, y: u16, color: Rgb565) {
-        let color = RawU16::from(color).into_inner();
-        let idx = match self.orientation {
-            Orientation::Landscape | Orientation::LandscapeSwapped => {
-                if x as u32 >= self.width {
-                    return;
-                }
-                ((y as usize) * self.width as usize) + (x as usize)
-            }
+    /// Changes the orientation the frame is drawn in, swapping width and height if needed.
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        (self.width, self.height) = self
+            .orientation
+            .reorient(orientation, (self.width, self.height));
+        self.orientation = orientation;
+    }
 
-            Orientation::Portrait | Orientation::PortraitSwapped => {
-                if y as u32 >= self.width as u32 {
-                    return;
-                }
-                ((y as usize) * self.height as usize) + (x as usize)
+    pub fn set_pixel(&mut self, x: u16, y: u16, color: C) {
+        match pixel_index(self.width, self.height, x, y) {
+            Some(idx) if C::bytes(idx + 1) <= self.buffer.len() => {
+                color.store(&mut self.buffer, idx)
             }
-        } * 2;
+            _ => {}
+        }
+    }
 
-        // Split 16 bit value into two bytes
-        let low = (color & 0xff) as u8;
-        let high = ((color & 0xff00) >> 8) as u8;
-        if idx >= self.buffer.len() - 1 {
-            return;
+    /// Fails with [`DrawError::OutOfRange`] if `width` x `height` pixels don't fit in the buffer.
+    fn check_buffer(&self) -> Result<(), DrawError> {
+        if C::bytes(self.width as usize * self.height as usize) > N {
+            return Err(DrawError::OutOfRange);
         }
-        self.buffer[idx] = high;
-        self.buffer[idx + 1] = low;
+        Ok(())
     }
 }
-impl<const N: usize> Default for Frame<N> {
+impl<const N: usize, C> Default for Frame<N, C> {
     fn default() -> Self {
         Self {
             width: 160,
             height: 128,
             orientation: Orientation::Landscape,
             buffer: [0; N],
+            color: PhantomData,
         }
     }
 }
 
-impl<const N: usize> DrawTarget for Frame<N> {
-    type Error = ();
-    type Color = Rgb565;
+impl<const N: usize, C: DisplayColor> DrawTarget for Frame<N, C> {
+    /// Pixels outside of the frame are dropped, drawing fails if the frame is larger than its
+    /// buffer.
+    type Error = DrawError;
+    type Color = C;
 
     fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
     where
         I: IntoIterator<Item = Pixel<Self::Color>>,
     {
+        self.check_buffer()?;
         let bb = self.bounding_box();
         pixels
             .into_iter()
@@ -496,22 +1692,288 @@ impl<const N: usize> DrawTarget for Frame<N> {
         Ok(())
     }
 
-    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
-        let c = RawU16::from(color).into_inner();
-        for i in 0..BUF_SIZE {
-            assert!(i < self.buffer.len());
-            self.buffer[i] = if i % 2 == 0 {
-                ((c & 0xff00) >> 8) as u8
-            } else {
-                (c & 0xff) as u8
-            };
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        self.check_buffer()?;
+        if area.intersection(&self.bounding_box()) != *area {
+            return self.draw_iter(area.points().zip(colors).map(|(p, c)| Pixel(p, c)));
+        }
+        if let Some(rect) = Rect::from_area(area) {
+            copy_rect(&mut self.buffer, self.width, rect, colors);
         }
         Ok(())
     }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.check_buffer()?;
+        if let Some(rect) = Rect::from_area(&area.intersection(&self.bounding_box())) {
+            fill_rect(&mut self.buffer, self.width, rect, color);
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.fill_solid(&self.bounding_box(), color)
+    }
 }
 
-impl<const N: usize> OriginDimensions for Frame<N> {
+impl<const N: usize, C> OriginDimensions for Frame<N, C> {
     fn size(&self) -> Size {
         Size::new(self.width, self.height)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIENTATIONS: [Orientation; 4] = [
+        Orientation::Portrait,
+        Orientation::Landscape,
+        Orientation::PortraitSwapped,
+        Orientation::LandscapeSwapped,
+    ];
+
+    /// Sets every pixel of a 4x3 landscape frame turned to `orientation` and checks where it
+    /// lands in the buffer.
+    fn check_orientation(orientation: Orientation) {
+        let mut frame = Frame::new(4, 3, Orientation::Landscape, [0; 4 * 3 * 2]);
+        frame.set_orientation(orientation);
+        let (width, height) = if orientation.swap_axes() {
+            (4, 3)
+        } else {
+            (3, 4)
+        };
+        assert_eq!(frame.size(), Size::new(width, height));
+
+        for y in 0..height {
+            for x in 0..width {
+                frame.clear(Rgb565::BLACK).unwrap();
+                frame.set_pixel(x as u16, y as u16, Rgb565::WHITE);
+                let idx = ((y * width + x) * 2) as usize;
+                assert_eq!(frame.buffer[idx..idx + 2], [0xFF, 0xFF]);
+                assert_eq!(frame.buffer.iter().filter(|&&b| b != 0).count(), 2);
+            }
+        }
+
+        frame.clear(Rgb565::BLACK).unwrap();
+        frame.set_pixel(width as u16, 0, Rgb565::WHITE);
+        frame.set_pixel(0, height as u16, Rgb565::WHITE);
+        frame
+            .draw_iter([
+                Pixel(Point::new(width as i32, height as i32 - 1), Rgb565::WHITE),
+                Pixel(Point::new(-1, 0), Rgb565::WHITE),
+            ])
+            .unwrap();
+        assert!(frame.buffer.iter().all(|&b| b == 0));
+
+        frame
+            .draw_iter([Pixel(
+                Point::new(width as i32 - 1, height as i32 - 1),
+                Rgb565::WHITE,
+            )])
+            .unwrap();
+        assert_eq!(frame.buffer[frame.buffer.len() - 2..], [0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn portrait() {
+        check_orientation(Orientation::Portrait);
+    }
+
+    #[test]
+    fn landscape() {
+        check_orientation(Orientation::Landscape);
+    }
+
+    #[test]
+    fn portrait_swapped() {
+        check_orientation(Orientation::PortraitSwapped);
+    }
+
+    #[test]
+    fn landscape_swapped() {
+        check_orientation(Orientation::LandscapeSwapped);
+    }
+
+    /// Sets every pixel of a 4x3 landscape `ST7735` turned to each orientation and checks that
+    /// it lands where it does in a `Frame` turned the same way.
+    #[test]
+    fn st7735_shares_the_frame_mapping() {
+        let log = mock::Log::new();
+        let config = Config::builder(model::ST7735)
+            .size(3, 4)
+            .orientation(Orientation::Landscape)
+            .build()
+            .unwrap();
+        let display: ST7735<_, _, _, 4, 3, _, model::ST7735, Rgb565, Uninitialized> = ST7735::new(
+            mock::Spi(&log),
+            mock::Dc(&log),
+            mock::Rst,
+            config,
+            [0; 4 * 3 * 2],
+        );
+        let mut display = mock::block_on(display.init(&mut mock::Delay)).unwrap();
+
+        for orientation in ORIENTATIONS {
+            mock::block_on(display.set_orientation(orientation)).unwrap();
+            let mut frame = Frame::new(4, 3, Orientation::Landscape, [0; 4 * 3 * 2]);
+            frame.set_orientation(orientation);
+            assert_eq!(display.size(), frame.size());
+
+            let Size { width, height } = frame.size();
+            log.clear();
+            for y in 0..height as u16 {
+                for x in 0..width as u16 {
+                    display.buffer = [0; 4 * 3 * 2];
+                    frame.clear(Rgb565::BLACK).unwrap();
+                    display.set_pixel(x, y, Rgb565::WHITE);
+                    frame.set_pixel(x, y, Rgb565::WHITE);
+                    assert_eq!(
+                        display.buffer, frame.buffer,
+                        "orientation {} ({}, {})",
+                        orientation as u8, x, y
+                    );
+                    let idx = (y as usize * width as usize + x as usize) * 2;
+                    assert_eq!(display.buffer[idx..idx + 2], [0xFF, 0xFF]);
+                }
+            }
+            display.set_pixel(width as u16, 0, Rgb565::WHITE);
+            display.set_pixel(0, height as u16, Rgb565::WHITE);
+            assert_eq!(display.buffer, frame.buffer);
+            // Drawing only touches the framebuffer.
+            assert_eq!(log.with(|bytes| bytes.len()), 0);
+        }
+    }
+
+    #[test]
+    fn init_resets_the_controller_once() {
+        let swresets = |log: &mock::Log| {
+            log.with(|bytes| {
+                bytes
+                    .iter()
+                    .filter(|&&(dc, byte)| !dc && byte == Instruction::SWRESET as u8)
+                    .count()
+            })
+        };
+
+        let log = mock::Log::new();
+        let iface = ST7735IF::new(
+            mock::Spi(&log),
+            mock::Dc(&log),
+            mock::Rst,
+            Default::default(),
+        );
+        mock::block_on(iface.init(&mut mock::Delay)).unwrap();
+        assert_eq!(swresets(&log), 0);
+
+        let log = mock::Log::new();
+        let iface =
+            ST7735IF::new_without_reset(mock::Spi(&log), mock::Dc(&log), Default::default());
+        mock::block_on(iface.init(&mut mock::Delay)).unwrap();
+        assert_eq!(swresets(&log), 1);
+    }
+
+    #[test]
+    fn frame_flushes_reject_bad_geometry() {
+        let log = mock::Log::new();
+        let iface = ST7735IF::new(
+            mock::Spi(&log),
+            mock::Dc(&log),
+            mock::Rst,
+            Default::default(),
+        );
+        let mut iface = mock::block_on(iface.init(&mut mock::Delay)).unwrap();
+        let short = Frame::<8, Rgb565>::new(10, 10, Orientation::Landscape, [0; 8]);
+        let empty = Frame::<8, Rgb565>::new(0, 4, Orientation::Landscape, [0; 8]);
+
+        log.clear();
+        for frame in [&short, &empty] {
+            assert!(matches!(
+                mock::block_on(iface.flush_frame(frame)),
+                Err(Error::InvalidGeometry)
+            ));
+            assert!(matches!(
+                mock::block_on(iface.flush_frame_scaled(frame, Scale::X2)),
+                Err(Error::InvalidGeometry)
+            ));
+        }
+        assert_eq!(log.with(|bytes| bytes.len()), 0);
+
+        assert_eq!(frame_end(160, 128, 1), Some((159, 127)));
+        assert_eq!(frame_end(40, 32, 4), Some((159, 127)));
+        assert_eq!(frame_end(0, 128, 1), None);
+        assert_eq!(frame_end(20_000, 1, 4), None);
+    }
+
+    #[test]
+    fn fills_match_per_pixel_drawing() {
+        let areas = [
+            Rectangle::new(Point::new(1, 1), Size::new(2, 2)),
+            Rectangle::new(Point::new(2, -1), Size::new(5, 3)),
+            Rectangle::new(Point::new(0, 0), Size::new(0, 3)),
+        ];
+        let colors = |area: &Rectangle| {
+            (0..area.size.width * area.size.height).map(|i| Rgb565::new(i as u8, 0, 0))
+        };
+        for area in &areas {
+            let mut expected = Frame::new(4, 3, Orientation::Landscape, [0; 4 * 3 * 2]);
+            let mut frame = Frame::new(4, 3, Orientation::Landscape, [0; 4 * 3 * 2]);
+
+            expected
+                .draw_iter(area.points().map(|p| Pixel(p, Rgb565::WHITE)))
+                .unwrap();
+            frame.fill_solid(area, Rgb565::WHITE).unwrap();
+            assert_eq!(frame.buffer, expected.buffer);
+
+            expected
+                .draw_iter(area.points().zip(colors(area)).map(|(p, c)| Pixel(p, c)))
+                .unwrap();
+            frame.fill_contiguous(area, colors(area)).unwrap();
+            assert_eq!(frame.buffer, expected.buffer);
+        }
+    }
+
+    #[test]
+    fn rgb444_fills_keep_neighbouring_pixels() {
+        // Odd width, so rows start alternately on a byte and in the middle of one.
+        let areas = [
+            Rectangle::new(Point::new(0, 0), Size::new(5, 3)),
+            Rectangle::new(Point::new(1, 0), Size::new(3, 3)),
+            Rectangle::new(Point::new(1, 1), Size::new(1, 2)),
+            Rectangle::new(Point::new(2, 0), Size::new(2, 3)),
+        ];
+        for area in &areas {
+            let mut expected = Frame::new(5, 3, Orientation::Landscape, [0xa5; 23]);
+            let mut frame = Frame::new(5, 3, Orientation::Landscape, [0xa5; 23]);
+
+            expected
+                .draw_iter(area.points().map(|p| Pixel(p, Rgb444::new(3, 12, 6))))
+                .unwrap();
+            frame.fill_solid(area, Rgb444::new(3, 12, 6)).unwrap();
+            assert_eq!(frame.buffer, expected.buffer, "{:?}", area);
+        }
+    }
+
+    #[test]
+    fn reorient_round_trips() {
+        for from in ORIENTATIONS {
+            for to in ORIENTATIONS {
+                let size = from.reorient(to, (160, 128));
+                assert_eq!(to.reorient(from, size), (160, 128));
+            }
+        }
+    }
+
+    #[test]
+    fn ram_offset_follows_mirroring() {
+        // 240x240 panel at the top of a 240x320 frame memory.
+        let offset = |o: Orientation| o.ram_offset((240, 320), (240, 240), (0, 0));
+        assert_eq!(offset(Orientation::Portrait), (0, 0));
+        assert_eq!(offset(Orientation::Landscape), (0, 0));
+        assert_eq!(offset(Orientation::PortraitSwapped), (0, 80));
+        assert_eq!(offset(Orientation::LandscapeSwapped), (80, 0));
+    }
+}
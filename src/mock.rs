@@ -0,0 +1,211 @@
+//! SPI device, pins and delay for host tests, recording what is sent to the controller.
+
+use core::cell::{Cell, RefCell};
+use core::convert::Infallible;
+use core::future::{ready, Future, Ready};
+use core::pin::Pin;
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal_async::delay::DelayUs;
+use embedded_hal_async::spi::{ErrorType, SpiBus, SpiBusFlush, SpiBusRead, SpiBusWrite, SpiDevice};
+
+/// Number of bytes a [`Log`] keeps.
+const LOG_LEN: usize = 4096;
+
+/// Bytes sent to the controller, each with the level of the DC pin while it was sent.
+pub struct Log {
+    dc: Cell<bool>,
+    bytes: RefCell<[(bool, u8); LOG_LEN]>,
+    len: Cell<usize>,
+}
+
+impl Log {
+    pub fn new() -> Self {
+        Self {
+            dc: Cell::new(false),
+            bytes: RefCell::new([(false, 0); LOG_LEN]),
+            len: Cell::new(0),
+        }
+    }
+
+    /// Forgets what was sent so far.
+    pub fn clear(&self) {
+        self.len.set(0);
+    }
+
+    /// Calls `f` with the bytes sent so far.
+    pub fn with<R>(&self, f: impl FnOnce(&[(bool, u8)]) -> R) -> R {
+        f(&self.bytes.borrow()[..self.len.get()])
+    }
+
+    fn push(&self, byte: u8) {
+        let len = self.len.get();
+        assert!(len < LOG_LEN, "mock log is full");
+        self.bytes.borrow_mut()[len] = (self.dc.get(), byte);
+        self.len.set(len + 1);
+    }
+}
+
+/// SPI device writing into a [`Log`].
+pub struct Spi<'a>(pub &'a Log);
+
+impl ErrorType for Spi<'_> {
+    type Error = Infallible;
+}
+
+unsafe impl<'l> SpiDevice for Spi<'l> {
+    type Bus = Spi<'l>;
+    type TransactionFuture<'a, R, F, Fut>
+        = Fut
+    where
+        Self: 'a,
+        R: 'a,
+        F: FnOnce(*mut Self::Bus) -> Fut + 'a,
+        Fut: Future<Output = Result<R, Infallible>> + 'a;
+
+    fn transaction<'a, R, F, Fut>(&'a mut self, f: F) -> Self::TransactionFuture<'a, R, F, Fut>
+    where
+        R: 'a,
+        F: FnOnce(*mut Self::Bus) -> Fut + 'a,
+        Fut: Future<Output = Result<R, Infallible>> + 'a,
+    {
+        f(self)
+    }
+}
+
+impl SpiBusFlush for Spi<'_> {
+    type FlushFuture<'a>
+        = Ready<Result<(), Infallible>>
+    where
+        Self: 'a;
+
+    fn flush(&mut self) -> Self::FlushFuture<'_> {
+        ready(Ok(()))
+    }
+}
+
+impl SpiBusWrite for Spi<'_> {
+    type WriteFuture<'a>
+        = Ready<Result<(), Infallible>>
+    where
+        Self: 'a;
+
+    fn write<'a>(&'a mut self, words: &'a [u8]) -> Self::WriteFuture<'a> {
+        for &byte in words {
+            self.0.push(byte);
+        }
+        ready(Ok(()))
+    }
+}
+
+impl SpiBusRead for Spi<'_> {
+    type ReadFuture<'a>
+        = Ready<Result<(), Infallible>>
+    where
+        Self: 'a;
+
+    fn read<'a>(&'a mut self, _words: &'a mut [u8]) -> Self::ReadFuture<'a> {
+        ready(Ok(()))
+    }
+}
+
+impl SpiBus for Spi<'_> {
+    type TransferFuture<'a>
+        = Ready<Result<(), Infallible>>
+    where
+        Self: 'a;
+    type TransferInPlaceFuture<'a>
+        = Ready<Result<(), Infallible>>
+    where
+        Self: 'a;
+
+    fn transfer<'a>(
+        &'a mut self,
+        _read: &'a mut [u8],
+        write: &'a [u8],
+    ) -> Self::TransferFuture<'a> {
+        SpiBusWrite::write(self, write)
+    }
+
+    fn transfer_in_place<'a>(
+        &'a mut self,
+        _words: &'a mut [u8],
+    ) -> Self::TransferInPlaceFuture<'a> {
+        ready(Ok(()))
+    }
+}
+
+/// DC pin setting the level recorded with the bytes of a [`Log`].
+pub struct Dc<'a>(pub &'a Log);
+
+impl OutputPin for Dc<'_> {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.dc.set(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.dc.set(true);
+        Ok(())
+    }
+}
+
+/// Reset pin that does nothing.
+pub struct Rst;
+
+impl OutputPin for Rst {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Delay that returns at once.
+pub struct Delay;
+
+impl DelayUs for Delay {
+    type Error = Infallible;
+    type DelayUsFuture<'a> = Ready<Result<(), Infallible>>;
+    type DelayMsFuture<'a> = Ready<Result<(), Infallible>>;
+
+    fn delay_us(&mut self, _us: u32) -> Self::DelayUsFuture<'_> {
+        ready(Ok(()))
+    }
+
+    fn delay_ms(&mut self, _ms: u32) -> Self::DelayMsFuture<'_> {
+        ready(Ok(()))
+    }
+}
+
+/// Runs `future` to completion. The mock never waits, so it completes on the first poll.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = future;
+    // SAFETY: `future` is shadowed and never moved again.
+    match poll_once(unsafe { Pin::new_unchecked(&mut future) }) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("mock future did not complete"),
+    }
+}
+
+/// Polls `future` once with a waker that does nothing.
+pub fn poll_once<F: Future>(future: Pin<&mut F>) -> Poll<F::Output> {
+    fn raw_waker() -> RawWaker {
+        fn clone(_: *const ()) -> RawWaker {
+            raw_waker()
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+        RawWaker::new(core::ptr::null(), &VTABLE)
+    }
+    // SAFETY: the waker ignores its data pointer.
+    let waker = unsafe { Waker::from_raw(raw_waker()) };
+    future.poll(&mut Context::from_waker(&waker))
+}
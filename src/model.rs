@@ -0,0 +1,500 @@
+//! Display controller models.
+//!
+//! The SPI plumbing of the driver is shared by a family of MIPI-DCS style controllers that only
+//! differ in their power-on sequence, frame memory size and a few register details. Each
+//! supported controller is described by a type implementing [`Model`].
+
+use crate::instruction::Instruction;
+use crate::Orientation;
+
+/// A single command of an initialization sequence.
+#[derive(Clone, Copy)]
+pub struct Command<'a> {
+    /// Command byte, sent with DC low.
+    pub instruction: u8,
+    /// Parameter bytes, sent with DC high.
+    pub params: &'a [u8],
+    /// Time to wait after the command, in milliseconds.
+    pub delay_time: u32,
+}
+
+impl<'a> Command<'a> {
+    pub const fn new(instruction: u8, params: &'a [u8], delay_time: u32) -> Self {
+        Self {
+            instruction,
+            params,
+            delay_time,
+        }
+    }
+}
+
+/// Pixel formats selectable with `COLMOD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// 12 bits per pixel.
+    Rgb444,
+    /// 16 bits per pixel.
+    Rgb565,
+    /// 18 bits per pixel.
+    Rgb666,
+}
+
+/// Description of a display controller.
+pub trait Model {
+    /// Module revisions that need a different initialization sequence or geometry.
+    type Variant: Copy + Default;
+
+    /// Width of the controller's frame memory in the native (portrait) orientation.
+    const RAM_WIDTH: u16;
+    /// Height of the controller's frame memory in the native (portrait) orientation.
+    const RAM_HEIGHT: u16;
+
+    /// MADCTL row address order bit.
+    const MADCTL_MY: u8 = 0x80;
+    /// MADCTL column address order bit.
+    const MADCTL_MX: u8 = 0x40;
+    /// MADCTL row/column exchange bit.
+    const MADCTL_MV: u8 = 0x20;
+    /// MADCTL BGR order bit.
+    const MADCTL_BGR: u8 = 0x08;
+
+    /// Color modes supported by the controller.
+    const COLOR_MODES: &'static [ColorMode];
+
+    /// Frame memory size used by `variant`, in the native orientation.
+    ///
+    /// Some modules strap the controller to a smaller memory that matches the panel, which
+    /// changes where the visible area ends up when an axis is mirrored.
+    fn ram_size(_variant: Self::Variant) -> (u16, u16) {
+        (Self::RAM_WIDTH, Self::RAM_HEIGHT)
+    }
+
+    /// Visible panel size of `variant`, in the native orientation.
+    fn size(variant: Self::Variant) -> (u16, u16);
+
+    /// Column/row offset of the visible area of `variant` in frame memory, in the native
+    /// orientation.
+    fn offset(variant: Self::Variant) -> (u16, u16);
+
+    /// Controller specific part of the initialization sequence, sent once
+    /// [`reset`](crate::ST7735IF::reset) has reset the controller and waited for it, so it starts
+    /// without `SWRESET`. Inversion, pixel format, orientation and `DISPON` are sent by the driver
+    /// afterwards.
+    fn init_sequence(variant: Self::Variant) -> &'static [Command<'static>];
+
+    /// MADCTL parameter for `orientation`.
+    fn madctl(orientation: Orientation, rgb: bool) -> u8 {
+        let mut madctl = if rgb { 0 } else { Self::MADCTL_BGR };
+        if orientation.mirror_y() {
+            madctl |= Self::MADCTL_MY;
+        }
+        if orientation.mirror_x() {
+            madctl |= Self::MADCTL_MX;
+        }
+        if orientation.swap_axes() {
+            madctl |= Self::MADCTL_MV;
+        }
+        madctl
+    }
+
+    /// COLMOD parameter for `mode`.
+    fn colmod(mode: ColorMode) -> u8 {
+        match mode {
+            ColorMode::Rgb444 => 0x03,
+            ColorMode::Rgb565 => 0x05,
+            ColorMode::Rgb666 => 0x06,
+        }
+    }
+}
+
+/// Sitronix ST7735, 132x162 frame memory.
+pub struct ST7735;
+
+/// ST7735 module revisions.
+///
+/// The "tab" names refer to the color of the protective film tab shipped on Adafruit style
+/// 1.8" modules. Red tab modules usually need BGR color order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ST7735Variant {
+    /// Original ST7735B, 128x160.
+    ST7735B,
+    /// ST7735R green tab, 128x160.
+    GreenTab,
+    /// ST7735R green tab, 128x128 (1.44" modules).
+    GreenTab128x128,
+    /// ST7735R red tab, 128x160.
+    RedTab,
+    /// ST7735R black tab, 128x160.
+    #[default]
+    BlackTab,
+    /// ST7735S, 80x160 (0.96" modules).
+    ST7735S,
+}
+
+const ST7735B_INIT: &[Command<'static>] = &[
+    Command::new(Instruction::SLPOUT as u8, &[], 500),
+    Command::new(Instruction::FRMCTR1 as u8, &[0x00, 0x06, 0x03], 10),
+    Command::new(Instruction::DISSET5 as u8, &[0x15, 0x02], 0),
+    Command::new(Instruction::INVCTR as u8, &[0x00], 0),
+    Command::new(Instruction::PWCTR1 as u8, &[0x02, 0x70], 10),
+    Command::new(Instruction::PWCTR2 as u8, &[0x05], 0),
+    Command::new(Instruction::PWCTR3 as u8, &[0x01, 0x02], 0),
+    Command::new(Instruction::VMCTR1 as u8, &[0x3C, 0x38], 10),
+    Command::new(Instruction::PWCTR6 as u8, &[0x11, 0x15], 0),
+    Command::new(
+        Instruction::GMCTRP1 as u8,
+        &[
+            0x09, 0x16, 0x09, 0x20, 0x21, 0x1B, 0x13, 0x19, 0x17, 0x15, 0x1E, 0x2B, 0x04, 0x05,
+            0x02, 0x0E,
+        ],
+        0,
+    ),
+    Command::new(
+        Instruction::GMCTRN1 as u8,
+        &[
+            0x0B, 0x14, 0x08, 0x1E, 0x22, 0x1D, 0x18, 0x1E, 0x1B, 0x1A, 0x24, 0x2B, 0x06, 0x06,
+            0x02, 0x0F,
+        ],
+        10,
+    ),
+    Command::new(Instruction::NORON as u8, &[], 10),
+];
+
+const ST7735R_INIT: &[Command<'static>] = &[
+    Command::new(Instruction::SLPOUT as u8, &[], 500),
+    Command::new(Instruction::FRMCTR1 as u8, &[0x01, 0x2C, 0x2D], 0),
+    Command::new(Instruction::FRMCTR2 as u8, &[0x01, 0x2C, 0x2D], 0),
+    Command::new(
+        Instruction::FRMCTR3 as u8,
+        &[0x01, 0x2C, 0x2D, 0x01, 0x2C, 0x2D],
+        0,
+    ),
+    Command::new(Instruction::INVCTR as u8, &[0x07], 0),
+    Command::new(Instruction::PWCTR1 as u8, &[0xA2, 0x02, 0x84], 0),
+    Command::new(Instruction::PWCTR2 as u8, &[0xC5], 0),
+    Command::new(Instruction::PWCTR3 as u8, &[0x0A, 0x00], 0),
+    Command::new(Instruction::PWCTR4 as u8, &[0x8A, 0x2A], 0),
+    Command::new(Instruction::PWCTR5 as u8, &[0x8A, 0xEE], 0),
+    Command::new(Instruction::VMCTR1 as u8, &[0x0E], 0),
+    Command::new(
+        Instruction::GMCTRP1 as u8,
+        &[
+            0x02, 0x1C, 0x07, 0x12, 0x37, 0x32, 0x29, 0x2D, 0x29, 0x25, 0x2B, 0x39, 0x00, 0x01,
+            0x03, 0x10,
+        ],
+        0,
+    ),
+    Command::new(
+        Instruction::GMCTRN1 as u8,
+        &[
+            0x03, 0x1D, 0x07, 0x06, 0x2E, 0x2C, 0x29, 0x2D, 0x2E, 0x2E, 0x37, 0x3F, 0x00, 0x00,
+            0x02, 0x10,
+        ],
+        0,
+    ),
+    Command::new(Instruction::NORON as u8, &[], 10),
+];
+
+const ST7735S_INIT: &[Command<'static>] = &[
+    Command::new(Instruction::SLPOUT as u8, &[], 120),
+    Command::new(Instruction::FRMCTR1 as u8, &[0x05, 0x3C, 0x3C], 0),
+    Command::new(Instruction::FRMCTR2 as u8, &[0x05, 0x3C, 0x3C], 0),
+    Command::new(
+        Instruction::FRMCTR3 as u8,
+        &[0x05, 0x3C, 0x3C, 0x05, 0x3C, 0x3C],
+        0,
+    ),
+    Command::new(Instruction::INVCTR as u8, &[0x03], 0),
+    Command::new(Instruction::PWCTR1 as u8, &[0x28, 0x08, 0x04], 0),
+    Command::new(Instruction::PWCTR2 as u8, &[0xC0], 0),
+    Command::new(Instruction::PWCTR3 as u8, &[0x0D, 0x00], 0),
+    Command::new(Instruction::PWCTR4 as u8, &[0x8D, 0x2A], 0),
+    Command::new(Instruction::PWCTR5 as u8, &[0x8D, 0xEE], 0),
+    Command::new(Instruction::VMCTR1 as u8, &[0x1A], 0),
+    Command::new(
+        Instruction::GMCTRP1 as u8,
+        &[
+            0x04, 0x22, 0x07, 0x0A, 0x2E, 0x30, 0x25, 0x2A, 0x28, 0x26, 0x2E, 0x3A, 0x00, 0x01,
+            0x03, 0x13,
+        ],
+        0,
+    ),
+    Command::new(
+        Instruction::GMCTRN1 as u8,
+        &[
+            0x04, 0x16, 0x06, 0x0D, 0x2D, 0x26, 0x23, 0x27, 0x27, 0x25, 0x2D, 0x3B, 0x00, 0x01,
+            0x04, 0x13,
+        ],
+        0,
+    ),
+    Command::new(Instruction::NORON as u8, &[], 10),
+];
+
+impl Model for ST7735 {
+    type Variant = ST7735Variant;
+
+    const RAM_WIDTH: u16 = 132;
+    const RAM_HEIGHT: u16 = 162;
+    const COLOR_MODES: &'static [ColorMode] =
+        &[ColorMode::Rgb444, ColorMode::Rgb565, ColorMode::Rgb666];
+
+    fn ram_size(variant: ST7735Variant) -> (u16, u16) {
+        match variant {
+            ST7735Variant::RedTab | ST7735Variant::BlackTab => (128, 160),
+            ST7735Variant::GreenTab128x128 => (132, 132),
+            _ => (Self::RAM_WIDTH, Self::RAM_HEIGHT),
+        }
+    }
+
+    fn size(variant: ST7735Variant) -> (u16, u16) {
+        match variant {
+            ST7735Variant::GreenTab128x128 => (128, 128),
+            ST7735Variant::ST7735S => (80, 160),
+            _ => (128, 160),
+        }
+    }
+
+    fn offset(variant: ST7735Variant) -> (u16, u16) {
+        match variant {
+            ST7735Variant::ST7735B | ST7735Variant::GreenTab | ST7735Variant::GreenTab128x128 => {
+                (2, 1)
+            }
+            ST7735Variant::RedTab | ST7735Variant::BlackTab => (0, 0),
+            ST7735Variant::ST7735S => (26, 1),
+        }
+    }
+
+    fn init_sequence(variant: ST7735Variant) -> &'static [Command<'static>] {
+        match variant {
+            ST7735Variant::ST7735B => ST7735B_INIT,
+            ST7735Variant::ST7735S => ST7735S_INIT,
+            _ => ST7735R_INIT,
+        }
+    }
+}
+
+/// Sitronix ST7789, 240x320 frame memory. Defaults to the common 240x240 panel.
+pub struct ST7789;
+
+// ST7789 registers that are not part of the ST7735 command set.
+const ST7789_PORCTRL: u8 = 0xB2;
+const ST7789_GCTRL: u8 = 0xB7;
+const ST7789_VCOMS: u8 = 0xBB;
+const ST7789_LCMCTRL: u8 = 0xC0;
+const ST7789_VDVVRHEN: u8 = 0xC2;
+const ST7789_VRHS: u8 = 0xC3;
+const ST7789_VDVS: u8 = 0xC4;
+const ST7789_FRCTRL2: u8 = 0xC6;
+const ST7789_PWCTRL1: u8 = 0xD0;
+
+const ST7789_INIT: &[Command<'static>] = &[
+    Command::new(Instruction::SLPOUT as u8, &[], 120),
+    Command::new(ST7789_PORCTRL, &[0x0C, 0x0C, 0x00, 0x33, 0x33], 0),
+    Command::new(ST7789_GCTRL, &[0x35], 0),
+    Command::new(ST7789_VCOMS, &[0x19], 0),
+    Command::new(ST7789_LCMCTRL, &[0x2C], 0),
+    Command::new(ST7789_VDVVRHEN, &[0x01], 0),
+    Command::new(ST7789_VRHS, &[0x12], 0),
+    Command::new(ST7789_VDVS, &[0x20], 0),
+    Command::new(ST7789_FRCTRL2, &[0x0F], 0),
+    Command::new(ST7789_PWCTRL1, &[0xA4, 0xA1], 0),
+    Command::new(
+        Instruction::GMCTRP1 as u8,
+        &[
+            0xD0, 0x04, 0x0D, 0x11, 0x13, 0x2B, 0x3F, 0x54, 0x4C, 0x18, 0x0D, 0x0B, 0x1F, 0x23,
+        ],
+        0,
+    ),
+    Command::new(
+        Instruction::GMCTRN1 as u8,
+        &[
+            0xD0, 0x04, 0x0C, 0x11, 0x13, 0x2C, 0x3F, 0x44, 0x51, 0x2F, 0x1F, 0x1F, 0x20, 0x23,
+        ],
+        0,
+    ),
+    Command::new(Instruction::NORON as u8, &[], 10),
+];
+
+impl Model for ST7789 {
+    type Variant = ();
+
+    const RAM_WIDTH: u16 = 240;
+    const RAM_HEIGHT: u16 = 320;
+    const COLOR_MODES: &'static [ColorMode] =
+        &[ColorMode::Rgb444, ColorMode::Rgb565, ColorMode::Rgb666];
+
+    fn size(_: ()) -> (u16, u16) {
+        (240, 240)
+    }
+
+    fn offset(_: ()) -> (u16, u16) {
+        (0, 0)
+    }
+
+    fn init_sequence(_: ()) -> &'static [Command<'static>] {
+        ST7789_INIT
+    }
+
+    fn colmod(mode: ColorMode) -> u8 {
+        // The upper nibble selects the RGB interface format, which must match the MCU format.
+        match mode {
+            ColorMode::Rgb444 => 0x53,
+            ColorMode::Rgb565 => 0x55,
+            ColorMode::Rgb666 => 0x66,
+        }
+    }
+}
+
+/// Ilitek ILI9163, 128x160 frame memory. Defaults to the common 128x128 panel.
+pub struct ILI9163;
+
+// ILI9163 registers that are not part of the ST7735 command set.
+const ILI9163_GAMSET: u8 = 0x26;
+const ILI9163_VCOMOFFS: u8 = 0xC7;
+const ILI9163_GAMRSEL: u8 = 0xF2;
+
+const ILI9163_INIT: &[Command<'static>] = &[
+    Command::new(Instruction::SLPOUT as u8, &[], 120),
+    Command::new(ILI9163_GAMSET, &[0x04], 0),
+    Command::new(ILI9163_GAMRSEL, &[0x01], 0),
+    Command::new(
+        Instruction::GMCTRP1 as u8,
+        &[
+            0x3F, 0x25, 0x1C, 0x1E, 0x20, 0x12, 0x2A, 0x90, 0x24, 0x11, 0x00, 0x00, 0x00, 0x00,
+            0x00,
+        ],
+        0,
+    ),
+    Command::new(
+        Instruction::GMCTRN1 as u8,
+        &[
+            0x20, 0x20, 0x20, 0x20, 0x05, 0x00, 0x15, 0xA7, 0x3D, 0x18, 0x25, 0x2A, 0x2B, 0x2B,
+            0x3A,
+        ],
+        0,
+    ),
+    Command::new(Instruction::FRMCTR1 as u8, &[0x08, 0x08], 0),
+    Command::new(Instruction::INVCTR as u8, &[0x07], 0),
+    Command::new(Instruction::PWCTR1 as u8, &[0x0A, 0x02], 0),
+    Command::new(Instruction::PWCTR2 as u8, &[0x02], 0),
+    Command::new(Instruction::VMCTR1 as u8, &[0x50, 0x5B], 0),
+    Command::new(ILI9163_VCOMOFFS, &[0x40], 0),
+];
+
+impl Model for ILI9163 {
+    type Variant = ();
+
+    const RAM_WIDTH: u16 = 128;
+    const RAM_HEIGHT: u16 = 160;
+    const COLOR_MODES: &'static [ColorMode] =
+        &[ColorMode::Rgb444, ColorMode::Rgb565, ColorMode::Rgb666];
+
+    fn size(_: ()) -> (u16, u16) {
+        (128, 128)
+    }
+
+    fn offset(_: ()) -> (u16, u16) {
+        (0, 0)
+    }
+
+    fn init_sequence(_: ()) -> &'static [Command<'static>] {
+        ILI9163_INIT
+    }
+}
+
+/// Galaxycore GC9A01, 240x240 frame memory for round panels.
+pub struct GC9A01;
+
+// Vendor sequence; most registers are undocumented and must be unlocked with 0xFE/0xEF.
+const GC9A01_INIT: &[Command<'static>] = &[
+    Command::new(0xEF, &[], 0),
+    Command::new(0xEB, &[0x14], 0),
+    Command::new(0xFE, &[], 0),
+    Command::new(0xEF, &[], 0),
+    Command::new(0xEB, &[0x14], 0),
+    Command::new(0x84, &[0x40], 0),
+    Command::new(0x85, &[0xFF], 0),
+    Command::new(0x86, &[0xFF], 0),
+    Command::new(0x87, &[0xFF], 0),
+    Command::new(0x88, &[0x0A], 0),
+    Command::new(0x89, &[0x21], 0),
+    Command::new(0x8A, &[0x00], 0),
+    Command::new(0x8B, &[0x80], 0),
+    Command::new(0x8C, &[0x01], 0),
+    Command::new(0x8D, &[0x01], 0),
+    Command::new(0x8E, &[0xFF], 0),
+    Command::new(0x8F, &[0xFF], 0),
+    Command::new(0xB6, &[0x00, 0x20], 0),
+    Command::new(0x90, &[0x08, 0x08, 0x08, 0x08], 0),
+    Command::new(0xBD, &[0x06], 0),
+    Command::new(0xBC, &[0x00], 0),
+    Command::new(0xFF, &[0x60, 0x01, 0x04], 0),
+    Command::new(0xC3, &[0x13], 0),
+    Command::new(0xC4, &[0x13], 0),
+    Command::new(0xC9, &[0x22], 0),
+    Command::new(0xBE, &[0x11], 0),
+    Command::new(0xE1, &[0x10, 0x0E], 0),
+    Command::new(0xDF, &[0x21, 0x0C, 0x02], 0),
+    Command::new(0xF0, &[0x45, 0x09, 0x08, 0x08, 0x26, 0x2A], 0),
+    Command::new(0xF1, &[0x43, 0x70, 0x72, 0x36, 0x37, 0x6F], 0),
+    Command::new(0xF2, &[0x45, 0x09, 0x08, 0x08, 0x26, 0x2A], 0),
+    Command::new(0xF3, &[0x43, 0x70, 0x72, 0x36, 0x37, 0x6F], 0),
+    Command::new(0xED, &[0x1B, 0x0B], 0),
+    Command::new(0xAE, &[0x77], 0),
+    Command::new(0xCD, &[0x63], 0),
+    Command::new(
+        0x70,
+        &[0x07, 0x07, 0x04, 0x0E, 0x0F, 0x09, 0x07, 0x08, 0x03],
+        0,
+    ),
+    Command::new(0xE8, &[0x34], 0),
+    Command::new(
+        0x62,
+        &[
+            0x18, 0x0D, 0x71, 0xED, 0x70, 0x70, 0x18, 0x0F, 0x71, 0xEF, 0x70, 0x70,
+        ],
+        0,
+    ),
+    Command::new(
+        0x63,
+        &[
+            0x18, 0x11, 0x71, 0xF1, 0x70, 0x70, 0x18, 0x13, 0x71, 0xF3, 0x70, 0x70,
+        ],
+        0,
+    ),
+    Command::new(0x64, &[0x28, 0x29, 0xF1, 0x01, 0xF1, 0x00, 0x07], 0),
+    Command::new(
+        0x66,
+        &[0x3C, 0x00, 0xCD, 0x67, 0x45, 0x45, 0x10, 0x00, 0x00, 0x00],
+        0,
+    ),
+    Command::new(
+        0x67,
+        &[0x00, 0x3C, 0x00, 0x00, 0x00, 0x01, 0x54, 0x10, 0x32, 0x98],
+        0,
+    ),
+    Command::new(0x74, &[0x10, 0x85, 0x80, 0x00, 0x00, 0x4E, 0x00], 0),
+    Command::new(0x98, &[0x3E, 0x07], 0),
+    Command::new(Instruction::SLPOUT as u8, &[], 120),
+];
+
+impl Model for GC9A01 {
+    type Variant = ();
+
+    const RAM_WIDTH: u16 = 240;
+    const RAM_HEIGHT: u16 = 240;
+    const COLOR_MODES: &'static [ColorMode] =
+        &[ColorMode::Rgb444, ColorMode::Rgb565, ColorMode::Rgb666];
+
+    fn size(_: ()) -> (u16, u16) {
+        (240, 240)
+    }
+
+    fn offset(_: ()) -> (u16, u16) {
+        (0, 0)
+    }
+
+    fn init_sequence(_: ()) -> &'static [Command<'static>] {
+        GC9A01_INIT
+    }
+}
@@ -0,0 +1,409 @@
+//! Double and triple buffering of [`Frame`]s between a drawing and a flushing task.
+
+use core::cell::UnsafeCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use core::task::{Context, Poll, Waker};
+
+use embedded_graphics_core::pixelcolor::Rgb565;
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal_async::spi::{SpiBus, SpiDevice};
+
+use crate::{model, DisplayColor, Error, Frame, FrameDiff, Model, ST7735IF};
+
+/// Owns `F` frames and the display, passing frames from a drawing task to a flushing task.
+///
+/// [`split`](Self::split) it into a [`Canvas`] to draw on and a [`Flusher`] to run in a
+/// background task. Finished frames are queued with [`Canvas::present`], the flusher always
+/// sends the newest one and drops those it did not get to. With two frames the canvas waits for
+/// every flush, a third frame lets it draw while one frame is sent and another is queued.
+///
+/// Only available on targets with atomic compare-and-swap.
+pub struct Presenter<SPI, DC, RST, const N: usize, const F: usize, M = model::ST7735, C = Rgb565>
+where
+    SPI: SpiDevice,
+    SPI::Bus: SpiBus,
+    DC: OutputPin,
+    RST: OutputPin,
+    M: Model,
+{
+    iface: ST7735IF<SPI, DC, RST, M>,
+    shared: Shared<N, F, C>,
+}
+
+impl<SPI, DC, RST, const N: usize, const F: usize, M, C, E, DCE, RSTE>
+    Presenter<SPI, DC, RST, N, F, M, C>
+where
+    SPI: SpiDevice<Error = E>,
+    SPI::Bus: SpiBus,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    M: Model,
+    C: DisplayColor,
+{
+    /// Creates a presenter sending `frames` to `iface`, which must be initialized.
+    ///
+    /// Latencies are measured in ticks of `clock`, e.g. `|| Instant::now().as_micros() as u32`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are less than two frames.
+    pub fn new(
+        iface: ST7735IF<SPI, DC, RST, M>,
+        frames: [Frame<N, C>; F],
+        clock: fn() -> u32,
+    ) -> Self {
+        assert!(F >= 2, "presenter needs at least two frames");
+        Self {
+            iface,
+            shared: Shared {
+                frames: UnsafeCell::new(frames),
+                presented_at: UnsafeCell::new([0; F]),
+                presented: AtomicU32::new(0),
+                flushed: AtomicU32::new(0),
+                dropped: AtomicU32::new(0),
+                back: AtomicUsize::new(0),
+                front: AtomicUsize::new(0),
+                last_latency: AtomicU32::new(0),
+                max_latency: AtomicU32::new(0),
+                clock,
+                canvas_waker: WakerCell::new(),
+                flusher_waker: WakerCell::new(),
+            },
+        }
+    }
+
+    /// Splits the presenter into its drawing and flushing halves.
+    ///
+    /// Splitting again continues where the previous halves stopped.
+    pub fn split(&mut self) -> (Canvas<'_, N, F, C>, Flusher<'_, SPI, DC, RST, N, F, M, C>) {
+        (
+            Canvas {
+                shared: &self.shared,
+            },
+            Flusher {
+                iface: &mut self.iface,
+                shared: &self.shared,
+            },
+        )
+    }
+}
+
+/// Counters of a [`Presenter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PresenterStats {
+    /// Frames presented so far.
+    pub presented: u32,
+    /// Presented frames that were never sent, because a newer one was presented meanwhile.
+    pub dropped: u32,
+    /// Clock ticks from presenting the last sent frame until it was on the display.
+    pub last_latency: u32,
+    /// Highest latency so far.
+    pub max_latency: u32,
+}
+
+/// State shared by [`Canvas`] and [`Flusher`].
+///
+/// Frames are used in turn. Those presented but not flushed yet belong to the flusher, the one
+/// after them to the canvas.
+struct Shared<const N: usize, const F: usize, C> {
+    frames: UnsafeCell<[Frame<N, C>; F]>,
+    /// Clock reading when each frame was presented.
+    presented_at: UnsafeCell<[u32; F]>,
+    /// Frames presented so far, only written by the canvas.
+    presented: AtomicU32,
+    /// Frames flushed or dropped so far, only written by the flusher.
+    flushed: AtomicU32,
+    dropped: AtomicU32,
+    /// Frame being drawn, only written by the canvas.
+    back: AtomicUsize,
+    /// Oldest queued frame, only written by the flusher.
+    front: AtomicUsize,
+    last_latency: AtomicU32,
+    max_latency: AtomicU32,
+    clock: fn() -> u32,
+    /// Woken when a frame was flushed.
+    canvas_waker: WakerCell,
+    /// Woken when a frame was presented.
+    flusher_waker: WakerCell,
+}
+
+// SAFETY: frames and their timestamps are only accessed by the half that owns them, as tracked by
+// the `presented` and `flushed` counters.
+unsafe impl<const N: usize, const F: usize, C: Send> Sync for Shared<N, F, C> {}
+
+impl<const N: usize, const F: usize, C> Shared<N, F, C> {
+    /// Number of presented frames not flushed yet.
+    fn queued(&self) -> u32 {
+        let flushed = self.flushed.load(Ordering::SeqCst);
+        self.presented.load(Ordering::SeqCst).wrapping_sub(flushed)
+    }
+
+    fn stats(&self) -> PresenterStats {
+        PresenterStats {
+            presented: self.presented.load(Ordering::SeqCst),
+            dropped: self.dropped.load(Ordering::SeqCst),
+            last_latency: self.last_latency.load(Ordering::SeqCst),
+            max_latency: self.max_latency.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// Drawing half of a [`Presenter`].
+pub struct Canvas<'a, const N: usize, const F: usize, C = Rgb565> {
+    shared: &'a Shared<N, F, C>,
+}
+
+impl<const N: usize, const F: usize, C> Canvas<'_, N, F, C> {
+    /// Frame to draw the next picture into, once the flusher is done with it.
+    ///
+    /// It keeps what was drawn into it `F` frames before.
+    pub async fn back_buffer(&mut self) -> &mut Frame<N, C> {
+        self.wait_for_back_buffer().await;
+        let index = self.shared.back.load(Ordering::SeqCst);
+        // SAFETY: with less than `F` frames queued, the frame after them belongs to the canvas.
+        unsafe { &mut (*self.shared.frames.get())[index] }
+    }
+
+    /// Queues the back buffer to be sent and waits until the next one is free.
+    pub async fn present(&mut self) {
+        let shared = self.shared;
+        // A `present` dropped while waiting leaves the back buffer queued.
+        self.wait_for_back_buffer().await;
+        let index = shared.back.load(Ordering::SeqCst);
+        // SAFETY: the timestamp of the back buffer belongs to the canvas as well.
+        unsafe { (*shared.presented_at.get())[index] = (shared.clock)() };
+        shared.back.store((index + 1) % F, Ordering::SeqCst);
+        let presented = shared.presented.load(Ordering::SeqCst).wrapping_add(1);
+        shared.presented.store(presented, Ordering::SeqCst);
+        shared.flusher_waker.wake();
+        self.wait_for_back_buffer().await
+    }
+
+    /// Waits until less than `F` frames are queued, so the back buffer isn't one of them.
+    async fn wait_for_back_buffer(&self) {
+        let shared = self.shared;
+        WaitFor {
+            waker: &shared.canvas_waker,
+            ready: || shared.queued() < F as u32,
+        }
+        .await
+    }
+
+    pub fn stats(&self) -> PresenterStats {
+        self.shared.stats()
+    }
+}
+
+/// Flushing half of a [`Presenter`], meant to run in a background task.
+pub struct Flusher<'a, SPI, DC, RST, const N: usize, const F: usize, M = model::ST7735, C = Rgb565>
+where
+    SPI: SpiDevice,
+    SPI::Bus: SpiBus,
+    DC: OutputPin,
+    RST: OutputPin,
+    M: Model,
+{
+    iface: &'a mut ST7735IF<SPI, DC, RST, M>,
+    shared: &'a Shared<N, F, C>,
+}
+
+impl<'a, SPI, DC, RST, const N: usize, const F: usize, M, C, E, DCE, RSTE>
+    Flusher<'a, SPI, DC, RST, N, F, M, C>
+where
+    SPI: SpiDevice<Error = E>,
+    SPI::Bus: SpiBus,
+    DC: OutputPin<Error = DCE>,
+    RST: OutputPin<Error = RSTE>,
+    M: Model,
+    C: DisplayColor,
+{
+    /// Waits for a presented frame and sends it, dropping older queued frames.
+    pub async fn flush_next(&mut self) -> Result<(), Error<E, DCE, RSTE>> {
+        let frame = self.next().await;
+        let result = self.iface.flush_frame(frame).await;
+        self.release(result.is_ok());
+        result
+    }
+
+    /// Like [`flush_next`](Self::flush_next), but only sends the tiles that changed since the
+    /// last frame sent with `diff`, see [`ST7735IF::flush_frame_diff`].
+    pub async fn flush_next_diff<const T: usize>(
+        &mut self,
+        diff: &mut FrameDiff<T>,
+    ) -> Result<(), Error<E, DCE, RSTE>> {
+        let frame = self.next().await;
+        let result = self.iface.flush_frame_diff(frame, diff).await;
+        self.release(result.is_ok());
+        result
+    }
+
+    /// Waits for a presented frame and drops all but the newest one, which is returned.
+    async fn next(&mut self) -> &'a Frame<N, C> {
+        let shared = self.shared;
+        WaitFor {
+            waker: &shared.flusher_waker,
+            ready: || shared.queued() > 0,
+        }
+        .await;
+
+        let skipped = shared.queued() - 1;
+        if skipped > 0 {
+            let dropped = shared.dropped.load(Ordering::SeqCst);
+            shared
+                .dropped
+                .store(dropped.wrapping_add(skipped), Ordering::SeqCst);
+            let front = shared.front.load(Ordering::SeqCst);
+            shared
+                .front
+                .store((front + skipped as usize) % F, Ordering::SeqCst);
+            let flushed = shared.flushed.load(Ordering::SeqCst);
+            shared
+                .flushed
+                .store(flushed.wrapping_add(skipped), Ordering::SeqCst);
+            shared.canvas_waker.wake();
+        }
+        let index = shared.front.load(Ordering::SeqCst);
+        // SAFETY: queued frames belong to the flusher until `flushed` moves past them.
+        unsafe { &(*shared.frames.get())[index] }
+    }
+
+    /// Hands the frame returned by [`next`](Self::next) back to the canvas, recording its latency
+    /// if it was `sent`.
+    fn release(&mut self, sent: bool) {
+        let shared = self.shared;
+        let index = shared.front.load(Ordering::SeqCst);
+        if sent {
+            // SAFETY: the timestamp belongs to the flusher along with the frame.
+            let presented_at = unsafe { (*shared.presented_at.get())[index] };
+            let latency = (shared.clock)().wrapping_sub(presented_at);
+            shared.last_latency.store(latency, Ordering::SeqCst);
+            if latency > shared.max_latency.load(Ordering::SeqCst) {
+                shared.max_latency.store(latency, Ordering::SeqCst);
+            }
+        }
+        shared.front.store((index + 1) % F, Ordering::SeqCst);
+        let flushed = shared.flushed.load(Ordering::SeqCst);
+        shared
+            .flushed
+            .store(flushed.wrapping_add(1), Ordering::SeqCst);
+        shared.canvas_waker.wake();
+    }
+
+    pub fn stats(&self) -> PresenterStats {
+        self.shared.stats()
+    }
+}
+
+/// Waker of a task waiting on the other half of a presenter.
+struct WakerCell {
+    locked: AtomicBool,
+    waker: UnsafeCell<Option<Waker>>,
+}
+
+impl WakerCell {
+    const fn new() -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            waker: UnsafeCell::new(None),
+        }
+    }
+
+    /// Stores `waker` to be woken by [`wake`](Self::wake).
+    ///
+    /// The caller must check what it waits for again afterwards, a concurrent `wake` is lost.
+    fn register(&self, waker: &Waker) {
+        if self.locked.swap(true, Ordering::SeqCst) {
+            // Being woken right now, the old waker may be taken, poll again.
+            waker.wake_by_ref();
+            return;
+        }
+        // SAFETY: the lock is held.
+        let slot = unsafe { &mut *self.waker.get() };
+        match slot {
+            Some(old) if old.will_wake(waker) => {}
+            _ => *slot = Some(waker.clone()),
+        }
+        self.locked.store(false, Ordering::SeqCst);
+    }
+
+    fn wake(&self) {
+        if self.locked.swap(true, Ordering::SeqCst) {
+            // Being registered, the task checks its condition again afterwards.
+            return;
+        }
+        // SAFETY: the lock is held.
+        let waker = unsafe { (*self.waker.get()).take() };
+        self.locked.store(false, Ordering::SeqCst);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Completes once `ready` returns true, registering with `waker` to be polled again.
+struct WaitFor<'a, P> {
+    waker: &'a WakerCell,
+    ready: P,
+}
+
+impl<P: Fn() -> bool> Future for WaitFor<'_, P> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if (self.ready)() {
+            return Poll::Ready(());
+        }
+        self.waker.register(cx.waker());
+        if (self.ready)() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock, Orientation};
+    use core::pin::pin;
+    use embedded_graphics_core::pixelcolor::RgbColor;
+
+    #[test]
+    fn dropped_present_keeps_the_back_buffer_queued() {
+        let log = mock::Log::new();
+        let iface = ST7735IF::new(
+            mock::Spi(&log),
+            mock::Dc(&log),
+            mock::Rst,
+            Default::default(),
+        );
+        let iface = mock::block_on(iface.init(&mut mock::Delay)).unwrap();
+        let frame = || Frame::new(1, 1, Orientation::Landscape, [0; 2]);
+        let mut presenter = Presenter::new(iface, [frame(), frame()], || 0);
+
+        let (mut canvas, _) = presenter.split();
+        mock::block_on(canvas.back_buffer()).set_pixel(0, 0, Rgb565::RED);
+        mock::block_on(canvas.present());
+        mock::block_on(canvas.back_buffer()).set_pixel(0, 0, Rgb565::GREEN);
+        // Both frames are queued, drop the present waiting for one of them.
+        assert!(mock::poll_once(pin!(canvas.present())).is_pending());
+        assert!(mock::poll_once(pin!(canvas.back_buffer())).is_pending());
+        assert_eq!(canvas.stats().presented, 2);
+
+        // Splitting again resumes with the queued frames.
+        let (mut canvas, mut flusher) = presenter.split();
+        assert!(mock::poll_once(pin!(canvas.back_buffer())).is_pending());
+        log.clear();
+        mock::block_on(flusher.flush_next()).unwrap();
+        // The newest frame was sent, the older one dropped.
+        let pixel = log.with(|bytes| [bytes[bytes.len() - 2].1, bytes[bytes.len() - 1].1]);
+        assert_eq!(Rgb565::load(&pixel, 0), Rgb565::GREEN);
+        assert_eq!(flusher.stats().dropped, 1);
+
+        let back = mock::block_on(canvas.back_buffer());
+        assert_eq!(Rgb565::load(&back.buffer, 0), Rgb565::RED);
+    }
+}
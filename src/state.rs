@@ -0,0 +1,13 @@
+//! Lifecycle states of [`ST7735IF`](crate::ST7735IF) and [`ST7735`](crate::ST7735).
+//!
+//! Drivers are created [`Uninitialized`], `init` turns them [`Ready`] and `sleep` and `wake` move
+//! between [`Ready`] and [`Sleeping`]. Only ready drivers draw and flush.
+
+/// Created, but not initialized yet. The panel may be in any state.
+pub struct Uninitialized;
+
+/// Initialized and awake, accepting drawing commands.
+pub struct Ready;
+
+/// In sleep mode, only waking up is allowed.
+pub struct Sleeping;